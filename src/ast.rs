@@ -39,18 +39,22 @@
  */
 pub mod canonical {
     use crate::grs::Types;
-    pub struct Node <NodeId, Val>(NodeId, Val, Vec<NodeId>);
-    pub struct Graph<NodeId, Val>(Vec<Node<NodeId, Val>>);
+
+    // A node is its id, its value, and the ids of its arguments. The
+    // first node in a graph is the root.
+    pub struct Node <NodeId, Val>(pub NodeId, pub Val, pub Vec<NodeId>);
+    pub struct Graph<NodeId, Val>(pub Vec<Node<NodeId, Val>>);
     pub type DataGraph<T: Types> = Graph<T::Id,  T::Val>;
     pub type Pattern  <T: Types> = Graph<T::Var, T::Val>;
 
+    // An empty contractum denotes a rule which only redirects.
     pub struct Rule<T: Types> {
-        redex: Pattern<T>,
-        contractum: Pattern<T>,
-        redirection: (T::Var, T::Var)
+        pub redex: Pattern<T>,
+        pub contractum: Pattern<T>,
+        pub redirection: (T::Var, T::Var)
     }
 
-    pub struct GRS<T: Types>(Vec<Rule<T>>);
+    pub struct GRS<T: Types>(pub Vec<Rule<T>>);
 }
 
 
@@ -24,7 +24,21 @@
 //
 // Fork this project to create your own MIT license that you can
 // always link to.
+use core::convert::TryFrom;
 use core::fmt::Debug;
+use core::iter::FromIterator;
+use crate::ast::canonical;
+use pattern::{PatternError, VecPattern};
+
+pub mod arena;
+pub mod eq;
+pub mod mapping;
+pub mod pattern;
+pub mod rc;
+pub mod reducer;
+pub mod strategy;
+pub mod trace;
+pub mod traverse;
 
 /**
  * This module provides the core traits for FGRS, and algorithms
@@ -45,6 +59,15 @@ pub trait SigmaRules: Sized {
     fn apply(_f: Self, _x: Self) -> Result<Self, Self::Error> {
         Err(Self::Error::default())
     }
+
+    // The fixed arity of this symbol, if it has one.
+    //
+    // Matching always requires a pattern node and a data node to
+    // agree on arity. Declaring it here additionally allows rules to
+    // be checked when they are constructed.
+    fn arity(&self) -> Option<usize> {
+        None
+    }
 }
 
 
@@ -65,13 +88,77 @@ pub trait Types {
 }
 
 
+/**
+ * Trait for ids which are small, dense unsigned integers.
+ *
+ * Arena-backed implementations use this to index directly into
+ * storage. The width of the integer bounds the number of nodes which
+ * can be allocated, so pick the smallest type that fits your problem.
+ */
+pub trait DenseId: Debug + Copy + PartialEq + 'static {
+    fn index(self) -> usize;
+    fn from_index(index: usize) -> Option<Self>;
+}
+
+macro_rules! dense_id {
+    ($($ty:ty),*) => {$(
+        impl DenseId for $ty {
+            fn index(self) -> usize { self as usize }
+            fn from_index(index: usize) -> Option<Self> {
+                <$ty>::try_from(index).ok()
+            }
+        }
+    )*}
+}
+
+dense_id!(u8, u16, u32, u64, usize);
+
+
 /**
  * This trait maps vars to IDs for rule rewriting.
+ *
+ * See the `mapping` module for implementations.
  */
 pub trait Mapping<T: Types>: Debug {
     fn new() -> Self;
-    fn get(&self, var: T::Var) -> T::Id;
+    fn try_get(&self, var: T::Var) -> Option<T::Id>;
     fn bind(&mut self, var: T::Var, id: T::Id);
+
+    fn get(&self, var: T::Var) -> T::Id {
+        self.try_get(var)
+            .unwrap_or_else(|| panic!("unbound var {:?}", var))
+    }
+}
+
+
+/**
+ * Reasons a reduction step can fail.
+ */
+#[derive(Debug, PartialEq)]
+pub enum ReduceError<T: Types> {
+    // No rule matches the indicated node.
+    NoMatch(T::Id),
+    // The contractum or redirection refers to a var which matching
+    // did not bind.
+    Unbound(T::Var),
+    // The indicated rule is not part of the GRS.
+    NoSuchRule(RuleIndex),
+}
+
+
+/**
+ * How a bounded reduction run ended.
+ */
+#[derive(Debug, PartialEq)]
+pub enum ReduceOutcome<T: Types> {
+    // The strategy found no further redex after `steps` steps.
+    NormalForm {steps: usize},
+    // The fuel ran out after `steps` steps, with redexes remaining.
+    OutOfFuel {steps: usize},
+    // The strategy indicated a node which is not a redex.
+    Stuck {node: T::Id},
+    // A reduction step failed.
+    Error(ReduceError<T>),
 }
 
 
@@ -136,9 +223,147 @@ pub trait DataGraphBody<'a, T: Types> {
     fn value(&'a self, id: T::Id) -> T::Val;
     fn alloc(&'a mut self, func: T::Val) -> T::Id;
     fn append_arg(&'a mut self, id: T::Id, arg: T::Id);
+    // Replace every reference to `src`, including the root and any
+    // pinned handles, with a reference to `dst`. Other references to
+    // `dst` are unaffected.
     fn redirect(&'a mut self, src: T::Id, dst: T::Id);
     fn root(&'a self) -> T::Id;
     fn gc(&'a mut self) {}
+
+    // Policy hook: return true when the graph would like `gc` to be
+    // called. The reduction loop checks this between steps, which is
+    // the only time it is safe to move or free nodes.
+    fn wants_gc(&'a self) -> bool { false }
+}
+
+
+/**
+ * Optional extension of `DataGraph` for graphs which index their
+ * reverse edges.
+ *
+ * The index is maintained incrementally by `append_arg` and
+ * `redirect`, which lets a graph redirect in time proportional to the
+ * number of referrers, rather than scanning every node.
+ */
+pub trait Referrers<T: Types>: DataGraph<T> {
+    // The nodes which have `id` as an argument, once per occurrence,
+    // in no particular order. The root and other external handles
+    // are not included.
+    fn referrers(&self, id: T::Id) -> &[T::Id];
+
+    // The number of argument positions which refer to `id`.
+    fn in_degree(&self, id: T::Id) -> usize {
+        self.referrers(id).len()
+    }
+}
+
+
+/**
+ * How a variable which occurs more than once in a redex is matched.
+ */
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    // Every occurrence must be bound to the same node. This is the
+    // usual semantics for graph rewriting.
+    #[default]
+    Identity,
+    // Occurrences may be bound to distinct nodes, so long as the
+    // subgraphs rooted there are structurally equal.
+    Structural,
+}
+
+
+impl MatchMode {
+    /**
+     * Returns true if `a` and `b` may be bound to the same var.
+     */
+    pub fn equivalent<T: Types>(
+        self,
+        data: &impl DataGraph<T>,
+        a: T::Id,
+        b: T::Id
+    ) -> bool {
+        match self {
+            MatchMode::Identity => a == b,
+            MatchMode::Structural => structurally_equal(data, a, b),
+        }
+    }
+}
+
+
+/**
+ * Returns true if the subgraphs rooted at `a` and `b` are
+ * structurally equal: they have the same values and arities, and
+ * their arguments are pairwise structurally equal.
+ *
+ * Cycles are handled coinductively, by the same walk as
+ * `eq::bisimilar`. Ids need not be dense here, so the pairs under
+ * comparison are kept in a list.
+ */
+pub fn structurally_equal<T: Types>(
+    data: &impl DataGraph<T>,
+    a: T::Id,
+    b: T::Id
+) -> bool {
+    let mut assumed: Vec<(T::Id, T::Id)> = Vec::new();
+    eq::bisimulate(data, a, data, b, |x, y| {
+        if x == y || assumed.contains(&(x, y)) {
+            false
+        } else {
+            assumed.push((x, y));
+            true
+        }
+    })
+}
+
+
+/**
+ * The ways in which a single pattern node can fail to match.
+ */
+#[derive(Debug, PartialEq)]
+pub enum Mismatch<T: Types> {
+    // The data node has a different symbol.
+    Symbol {expected: T::Val, found: T::Val},
+    // The data node has a different number of arguments.
+    Arity {expected: usize, found: usize},
+    // The var was already bound to a node which is not equivalent to
+    // this one under the rule's `MatchMode`.
+    Bound(T::Id),
+}
+
+
+/**
+ * Describes why a pattern failed to match: the pattern var and the
+ * data node at which matching stopped, and what was wrong there.
+ */
+#[derive(Debug, PartialEq)]
+pub struct MatchFailure<T: Types> {
+    pub var: T::Var,
+    pub node: T::Id,
+    pub mismatch: Mismatch<T>,
+}
+
+
+/**
+ * Observes the rewrite engine as it runs.
+ *
+ * Every event has a default no-op implementation, so a tracer only
+ * needs to implement the events it cares about, and `trace::NoTrace`
+ * compiles away entirely. See the `trace` module for implementations.
+ */
+pub trait Tracer<T: Types> {
+    // A rule is about to be matched at `node`.
+    fn attempt(&mut self, _node: T::Id) {}
+    // Matching bound `var` to `id`.
+    fn bind(&mut self, _var: T::Var, _id: T::Id) {}
+    // Matching failed.
+    fn fail(&mut self, _failure: &MatchFailure<T>) {}
+    // A contractum node was allocated.
+    fn alloc(&mut self, _id: T::Id) {}
+    // References to `src` were redirected to `dst`.
+    fn redirect(&mut self, _src: T::Id, _dst: T::Id) {}
+    // The garbage collector was run.
+    fn gc(&mut self) {}
 }
 
 
@@ -154,110 +379,371 @@ pub trait PatternBody<'a, T: Types> {
     fn root(&'a self) -> T::Var;
 
     // versions of this.
+    //
+    // Matching proceeds depth-first, left to right, and stops at the
+    // first failure.
     fn matches(
         &'a self,
         redex: T::Var,
         data: &impl DataGraph<T>,
         node: T::Id,
         mapping: &mut impl Mapping<T>,
-    ) -> Option<()> {
-        println!("enter: {:?}, {:?}", redex, node);
-
-        let redex_value = self.value(redex);
-        let node_value = data.value(node);
-
-        if redex_value == node_value {
-            println!("bind: {:?} -> {:?}", redex, node);
-            mapping.bind(redex, node);
-            let iter = self.args(redex).zip(data.args(node));
-            for (var, id) in iter {
-                println!("bind-rec: {:?}, {:?}", var, id);
-                if self.contains(var) {
-                    self.matches(var, data, id, mapping)?;
-                } else {
-                    mapping.bind(var, id);
+        mode: MatchMode,
+        tracer: &mut impl Tracer<T>,
+    ) -> Result<(), MatchFailure<T>> {
+        let mut fail = |mismatch| {
+            let failure = MatchFailure {var: redex, node, mismatch};
+            tracer.fail(&failure);
+            Err(failure)
+        };
+
+        // A pattern node we have already matched, reached again
+        // through a cycle or sharing in the pattern, must be revisited
+        // at the same data node. Stopping here is also what makes
+        // matching cyclic patterns terminate.
+        if let Some(bound) = mapping.try_get(redex) {
+            return if mode.equivalent(data, bound, node) {
+                Ok(())
+            } else {
+                fail(Mismatch::Bound(bound))
+            };
+        }
+
+        let expected = self.value(redex);
+        let found = data.value(node);
+        if expected != found {
+            return fail(Mismatch::Symbol {expected, found});
+        }
+
+        let expected = self.args(redex).count();
+        let found = data.args(node).count();
+        if expected != found {
+            return fail(Mismatch::Arity {expected, found});
+        }
+
+        tracer.bind(redex, node);
+        mapping.bind(redex, node);
+        let iter = self.args(redex).zip(data.args(node));
+        for (var, id) in iter {
+            if self.contains(var) {
+                self.matches(var, data, id, mapping, mode, tracer)?;
+            } else if let Some(bound) = mapping.try_get(var) {
+                // A repeated var must match what it matched before.
+                if !mode.equivalent(data, bound, id) {
+                    let failure = MatchFailure {
+                        var,
+                        node: id,
+                        mismatch: Mismatch::Bound(bound)
+                    };
+                    tracer.fail(&failure);
+                    return Err(failure);
                 }
-                println!("recurse-done {:?}", mapping);
+            } else {
+                tracer.bind(var, id);
+                mapping.bind(var, id);
             }
-            println!("success: {:?}", mapping);
-            Some(())
-        } else {
-            println!("fail: {:?} != {:?}", redex_value, node_value);
-            None
         }
+        Ok(())
     }
 
+    /**
+     * Instantiate the contractum rooted at `contractum` in `data`.
+     *
+     * Each node reachable from `contractum` is allocated exactly
+     * once, so sharing and cycles in the pattern are preserved in the
+     * data graph. Free vars are looked up in `mapping`, while the ids
+     * of the new nodes are recorded in `fresh`.
+     *
+     * Every free var is checked before anything is allocated, so a
+     * failed rewrite leaves `data` untouched.
+     */
     fn rewrite(
         &'a self,
         contractum: T::Var,
         data: &mut impl DataGraph<T>,
-        mapping: &impl Mapping<T>
-    ) -> T::Id {
-        let id = data.alloc(self.value(contractum));
-        for var in self.args(contractum) {
+        mapping: &impl Mapping<T>,
+        fresh: &mut impl Mapping<T>,
+        tracer: &mut impl Tracer<T>,
+    ) -> Result<T::Id, ReduceError<T>> {
+        let mut order = Vec::new();
+        let mut stack = vec![contractum];
+        while let Some(var) = stack.pop() {
             if self.contains(var) {
-                let arg_id = self.rewrite(var, data, mapping);
+                if !order.contains(&var) {
+                    order.push(var);
+                    stack.extend(self.args(var));
+                }
+            } else if mapping.try_get(var).is_none() {
+                return Err(ReduceError::Unbound(var));
+            }
+        }
+
+        for var in order.iter().copied() {
+            let id = data.alloc(self.value(var));
+            tracer.alloc(id);
+            fresh.bind(var, id);
+        }
+
+        for var in order.iter().copied() {
+            let id = fresh.get(var);
+            for arg in self.args(var) {
+                let arg_id = if self.contains(arg) {
+                    fresh.get(arg)
+                } else {
+                    mapping.get(arg)
+                };
                 data.append_arg(id, arg_id);
-            } else {
-                data.append_arg(id, mapping.get(var))
             }
         }
-        id
+
+        Ok(fresh.get(contractum))
+    }
+}
+
+
+/**
+ * Reasons a rule can't be constructed.
+ */
+#[derive(Debug, PartialEq)]
+pub enum RuleError<T: Types> {
+    // The canonical redex is not a valid pattern.
+    Redex(PatternError<T>),
+    // The canonical contractum is not a valid pattern.
+    Contractum(PatternError<T>),
+    // The contractum refers to a var which the redex doesn't bind.
+    UnboundVar(T::Var),
+    // One side of the redirection doesn't name a node.
+    DanglingRedirection(T::Var),
+    // A pattern node disagrees with the declared arity of its symbol.
+    Arity {var: T::Var, expected: usize, found: usize},
+    // The root of the redex is a free var, so the rule would match
+    // any node at all.
+    FreeRoot(T::Var),
+    // A contractum node can't be reached from the contractum root.
+    Unreachable(T::Var),
+}
+
+
+/**
+ * Check every node reachable in `pattern` against the arity declared
+ * for its symbol, if any.
+ */
+fn check_arity<T, P>(pattern: &P, defined: &[T::Var], errors: &mut Vec<RuleError<T>>)
+where T: Types, P: Pattern<T>
+{
+    for var in defined.iter().copied() {
+        if let Some(expected) = pattern.value(var).arity() {
+            let found = pattern.args(var).count();
+            if found != expected {
+                errors.push(RuleError::Arity {var, expected, found});
+            }
+        }
+    }
+}
+
+
+/**
+ * Collect every reason the given parts don't form a well-formed rule.
+ *
+ * See `Rule::new` for what is checked.
+ */
+fn diagnose<T, P>(
+    redex: &P,
+    contractum: Option<&P>,
+    redirection: (T::Var, T::Var)
+) -> Vec<RuleError<T>>
+where T: Types, P: Pattern<T>
+{
+    let mut errors = Vec::new();
+
+    if !redex.contains(redex.root()) {
+        errors.push(RuleError::FreeRoot(redex.root()));
+    }
+
+    let (mut bound, free) = scope(redex);
+    check_arity(redex, &bound, &mut errors);
+    bound.extend(free);
+
+    if let Some(contractum) = contractum {
+        let (defined, free) = scope(contractum);
+        check_arity(contractum, &defined, &mut errors);
+        errors.extend(
+            free.into_iter()
+                .filter(|v| !bound.contains(v))
+                .map(RuleError::UnboundVar)
+        );
+    }
+
+    let (src, dst) = redirection;
+    if !bound.contains(&src) {
+        errors.push(RuleError::DanglingRedirection(src));
+    }
+
+    let created = contractum.is_some_and(|c| scope(c).0.contains(&dst));
+    if !bound.contains(&dst) && !created {
+        errors.push(RuleError::DanglingRedirection(dst));
+    }
+
+    errors
+}
+
+
+/**
+ * Collect the vars which label nodes reachable from the root of
+ * `pattern`, and the free vars they refer to.
+ */
+fn scope<T, P>(pattern: &P) -> (Vec<T::Var>, Vec<T::Var>)
+where T: Types, P: Pattern<T>
+{
+    let mut defined = Vec::new();
+    let mut free = Vec::new();
+    let mut stack = vec![pattern.root()];
+
+    while let Some(var) = stack.pop() {
+        if pattern.contains(var) {
+            if !defined.contains(&var) {
+                defined.push(var);
+                stack.extend(pattern.args(var));
+            }
+        } else if !free.contains(&var) {
+            free.push(var);
+        }
     }
+
+    (defined, free)
 }
 
 
 /**
  * This is the generic rule struct which implements reduction.
+ *
+ * The contractum is optional: a rule without one simply redirects.
  */
 pub struct Rule<T, P> where T: Types, P: Pattern<T>{
     redex:       P,
-    contractum:  P,
-    redirection: (T::Var, T::Var)
+    contractum:  Option<P>,
+    redirection: (T::Var, T::Var),
+    mode:        MatchMode,
 }
 
 
 impl<T, P> Rule<T, P> where T: Types, P: Pattern<T> {
+    /**
+     * Construct a rule, checking that every var the contractum and
+     * redirection refer to is bound.
+     *
+     * The root of the redex must not be free, and every node must
+     * agree with the declared arity of its symbol. The source of the
+     * redirection must be bound by the redex. The target must either
+     * be bound by the redex, or be a node of the contractum.
+     *
+     * Only the first problem found is returned.
+     */
+    pub fn new(
+        redex: P,
+        contractum: Option<P>,
+        redirection: (T::Var, T::Var)
+    ) -> Result<Self, RuleError<T>> {
+        let errors = diagnose(&redex, contractum.as_ref(), redirection);
+        if let Some(err) = errors.into_iter().next() {
+            return Err(err);
+        }
+
+        Ok(Rule {redex, contractum, redirection, mode: MatchMode::default()})
+    }
+
+    /**
+     * Set how repeated vars in the redex are matched. The default is
+     * `MatchMode::Identity`.
+     */
+    pub fn with_mode(mut self, mode: MatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn redex(&self) -> &P {
+        &self.redex
+    }
+
+    pub fn contractum(&self) -> Option<&P> {
+        self.contractum.as_ref()
+    }
+
+    pub fn redirection(&self) -> (T::Var, T::Var) {
+        self.redirection
+    }
+
     /**
      * If a rule matches the subgraph rooted at `node`, return the
-     * mapping of variables to node ids.
+     * mapping of variables to node ids. Otherwise, describe where
+     * matching failed.
      */
-    pub fn matches<M: Mapping<T>>(&self, data: &impl DataGraph<T>, node: T::Id) -> Option<M> {
+    pub fn matches<M: Mapping<T>>(
+        &self,
+        data: &impl DataGraph<T>,
+        node: T::Id
+    ) -> Result<M, MatchFailure<T>> {
+        self.matches_traced(data, node, &mut trace::NoTrace)
+    }
+
+    fn matches_traced<M: Mapping<T>>(
+        &self,
+        data: &impl DataGraph<T>,
+        node: T::Id,
+        tracer: &mut impl Tracer<T>
+    ) -> Result<M, MatchFailure<T>> {
         let mut m = M::new();
-        if let Some(()) = self.redex.matches(
+        self.redex.matches(
             self.redex.root(),
             data,
             node,
-            &mut m
-        ) {
-            Some(m)
-        } else {
-            None
-        }
+            &mut m,
+            self.mode,
+            tracer
+        )?;
+        Ok(m)
     }
 
     /**
-     *
+     * If this rule matches the subgraph rooted at `node`, rewrite
+     * the contractum and perform the redirection.
      */
-    pub fn reduce<D, M>(&self, data: &mut D, node: T::Id) -> Option<()>
+    pub fn reduce<D, M>(
+        &self,
+        data: &mut D,
+        node: T::Id,
+        tracer: &mut impl Tracer<T>
+    ) -> Result<(), ReduceError<T>>
         where D: DataGraph<T>,
               M: Mapping<T>
     {
-        let map: Option<M> = self.matches(data, node);
-        if let Some(mapping) = map {
-            self.contractum.rewrite(self.contractum.root(), data, &mapping);
-            // XXX: this is an extra step, which ideally we could
-            // avoid by directly writing into the redirection node.
+        tracer.attempt(node);
+        let map: Result<M, _> = self.matches_traced(data, node, tracer);
+        if let Ok(mapping) = map {
+            let (src, dst) = self.redirection;
+            let mut dst_id = None;
+            if let Some(contractum) = &self.contractum {
+                let mut fresh = M::new();
+                contractum.rewrite(contractum.root(), data, &mapping, &mut fresh, tracer)?;
+                dst_id = fresh.try_get(dst);
+            }
+            let lookup = |var| mapping.try_get(var).ok_or(ReduceError::Unbound(var));
+            let src_id = lookup(src)?;
+            let dst_id = match dst_id {
+                Some(id) => id,
+                None => lookup(dst)?,
+            };
+            // Redirection is cheap in the library graphs: `VecGraph`
+            // installs an indirection rather than scanning for
+            // references.
             //
             // XXX: not clear we even need redirections given a
             // functional strategy.
-            data.redirect(
-                mapping.get(self.redirection.0),
-                mapping.get(self.redirection.1)
-            );
-            Some(())
+            tracer.redirect(src_id, dst_id);
+            data.redirect(src_id, dst_id);
+            Ok(())
         } else {
-            None
+            Err(ReduceError::NoMatch(node))
         }
     }
 }
@@ -268,14 +754,110 @@ impl<T, P> Rule<T, P> where T: Types, P: Pattern<T> {
  *
  * A serial strategy indicates one redex at a time. For now this is
  * all that's supported.
+ *
+ * See the `strategy` module for implementations.
  */
 pub trait Strategy<T: Types> {
     fn next_redex(&mut self, dg: &impl DataGraph<T>) -> Option<T::Id>;
 }
 
 
+/**
+ * The position of a rule within a GRS, in the order rules were added.
+ */
+pub type RuleIndex = usize;
+
+
+/**
+ * A strategy which indicates both the redex and the rule by which to
+ * reduce it, as in the literature.
+ *
+ * This allows for semantics other than rule priority, including
+ * nondeterministic ones. See `reduce_rules`, and
+ * `strategy::Priority` for an adapter which recovers the behavior of
+ * a plain `Strategy`.
+ */
+pub trait RuleStrategy<T: Types> {
+    fn next_step(&mut self, dg: &impl DataGraph<T>) -> Option<(T::Id, RuleIndex)>;
+}
+
+
+impl<T: Types> Rule<T, VecPattern<T>> where T::Var: 'static {
+    /**
+     * Report every problem with a rule in canonical form, rather than
+     * just the first.
+     *
+     * In addition to the checks made by `Rule::new`, this reports
+     * each var which labels more than one node, and each contractum
+     * node which can't be reached from the contractum root.
+     */
+    pub fn validate(rule: &canonical::Rule<T>) -> Vec<RuleError<T>> {
+        let mut errors: Vec<RuleError<T>> = VecPattern::validate(&rule.redex)
+            .into_iter()
+            .map(RuleError::Redex)
+            .collect();
+
+        let has_contractum = !rule.contractum.0.is_empty();
+        if has_contractum {
+            errors.extend(
+                VecPattern::validate(&rule.contractum)
+                    .into_iter()
+                    .map(RuleError::Contractum)
+            );
+        }
+
+        // Without a redex there is nothing more to check against.
+        if rule.redex.0.is_empty() {
+            return errors;
+        }
+
+        let redex = VecPattern::from_canonical(&rule.redex);
+        let contractum = if has_contractum {
+            Some(VecPattern::from_canonical(&rule.contractum))
+        } else {
+            None
+        };
+
+        if let Some(contractum) = &contractum {
+            let (reachable, _) = scope(contractum);
+            errors.extend(
+                contractum.vars()
+                    .filter(|var| !reachable.contains(var))
+                    .map(RuleError::Unreachable)
+            );
+        }
+
+        errors.extend(diagnose(&redex, contractum.as_ref(), rule.redirection));
+        errors
+    }
+}
+
+
+impl<T: Types> TryFrom<canonical::Rule<T>> for Rule<T, VecPattern<T>>
+where T::Var: 'static
+{
+    type Error = RuleError<T>;
+
+    fn try_from(rule: canonical::Rule<T>) -> Result<Self, RuleError<T>> {
+        if let Some(err) = Self::validate(&rule).into_iter().next() {
+            return Err(err);
+        }
+
+        let redex = VecPattern::new(rule.redex).map_err(RuleError::Redex)?;
+        let contractum = if rule.contractum.0.is_empty() {
+            None
+        } else {
+            Some(VecPattern::new(rule.contractum).map_err(RuleError::Contractum)?)
+        };
+        Rule::new(redex, contractum, rule.redirection)
+    }
+}
+
+
 /**
  * A complete GRS.
+ *
+ * Rules are tried in the order they were added.
  */
 pub struct GRS<T, P>(Vec<Rule<T, P>>)
 where T: Types, P: Pattern<T>;
@@ -283,6 +865,95 @@ where T: Types, P: Pattern<T>;
 
 impl<T, P> GRS<T, P>
 where T: Types, P: Pattern<T> {
+    pub fn new() -> Self {
+        GRS(Vec::new())
+    }
+
+    /**
+     * Builder-style version of `push`.
+     */
+    pub fn with_rule(mut self, rule: Rule<T, P>) -> Self {
+        self.push(rule);
+        self
+    }
+
+    pub fn push(&mut self, rule: Rule<T, P>) {
+        self.0.push(rule);
+    }
+
+    pub fn rules(&self) -> &[Rule<T, P>] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /**
+     * Return the index of the first rule which matches at `node`, if
+     * any.
+     */
+    pub fn matches<M: Mapping<T>>(
+        &self,
+        data: &impl DataGraph<T>,
+        node: T::Id
+    ) -> Option<RuleIndex> {
+        self.matching::<M, _>(data, node).next()
+    }
+
+    /**
+     * Return the indices of every rule which matches at `node`, in
+     * order.
+     */
+    pub fn matching<'s, M: Mapping<T>, D: DataGraph<T>>(
+        &'s self,
+        data: &'s D,
+        node: T::Id
+    ) -> impl Iterator<Item = RuleIndex> + 's {
+        self.0.iter()
+            .enumerate()
+            .filter(move |(_, rule)| rule.matches::<M>(data, node).is_ok())
+            .map(|(i, _)| i)
+    }
+
+    /**
+     * Try every rule at `node`, in order, reporting either the
+     * mapping it would bind or why it failed to match.
+     *
+     * This is meant for debugging rule sets: it answers the question
+     * "why didn't my rule fire here?".
+     */
+    pub fn explain<M: Mapping<T>>(
+        &self,
+        data: &impl DataGraph<T>,
+        node: T::Id
+    ) -> Vec<Result<M, MatchFailure<T>>> {
+        self.0.iter().map(|rule| rule.matches::<M>(data, node)).collect()
+    }
+
+    /**
+     * Perform one reduction step at `node` using exactly the rule
+     * given, rather than the first one which matches.
+     */
+    pub fn reduce_with<D, M>(
+        &self,
+        data: &mut D,
+        node: T::Id,
+        rule: RuleIndex,
+        tracer: &mut impl Tracer<T>
+    ) -> Result<(), ReduceError<T>> where
+        D: DataGraph<T>,
+        M: Mapping<T>
+    {
+        self.0.get(rule)
+            .ok_or(ReduceError::NoSuchRule(rule))?
+            .reduce::<D, M>(data, node, tracer)
+    }
+
     /**
      * Perform one reduction step on the the given datagraph using the
      * given strategy.
@@ -300,53 +971,138 @@ where T: Types, P: Pattern<T> {
      * something working for now, and I'm not sure how important this
      * distinction really is. It should be easy enough to change down
      * the road.
+     *
+     * Update: `reduce_with` and `RuleStrategy` now support the
+     * approach from the literature. This remains the common case.
+     *
+     * Returns the index of the rule which was applied.
      */
     pub fn reduce<D, M> (
         &self,
         data: &mut D,
-        node: T::Id
-    ) -> Option<()> where
+        node: T::Id,
+        tracer: &mut impl Tracer<T>
+    ) -> Result<RuleIndex, ReduceError<T>> where
         D: DataGraph<T>,
         M: Mapping<T>
     {
-        for rule in self.0.iter() {
-            if let Some(()) = rule.reduce::<D, M>(data, node) {
-                return Some(())
+        for (index, rule) in self.0.iter().enumerate() {
+            match rule.reduce::<D, M>(data, node, tracer) {
+                Err(ReduceError::NoMatch(_)) => continue,
+                result => return result.map(|()| index),
             }
         }
-        None
+        Err(ReduceError::NoMatch(node))
+    }
+}
+
+
+impl<T: Types> GRS<T, VecPattern<T>> where T::Var: 'static {
+    /**
+     * Report every problem with every rule of a GRS in canonical
+     * form, tagged with the index of the offending rule.
+     */
+    pub fn validate(grs: &canonical::GRS<T>) -> Vec<(RuleIndex, RuleError<T>)> {
+        grs.0.iter()
+            .enumerate()
+            .flat_map(|(index, rule)| {
+                Rule::validate(rule).into_iter().map(move |err| (index, err))
+            })
+            .collect()
+    }
+}
+
+
+impl<T, P> Default for GRS<T, P>
+where T: Types, P: Pattern<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl<T, P> FromIterator<Rule<T, P>> for GRS<T, P>
+where T: Types, P: Pattern<T> {
+    fn from_iter<I: IntoIterator<Item = Rule<T, P>>>(rules: I) -> Self {
+        GRS(rules.into_iter().collect())
     }
 }
 
 
 /**
  * Repeatedly reduce a datagraph until no further reductions are
- * indicated.
+ * indicated, or until `fuel` steps have been taken.
+ *
+ * Passing `None` for `fuel` runs without a bound, which may not
+ * terminate. The garbage collector is run between steps whenever the
+ * data graph asks for it.
+ *
+ * This is a convenience wrapper around `reducer::Reducer`, which
+ * callers can use directly to step through a reduction.
  */
 pub fn reduce<T, D, P, S, M>(
     grs: &GRS<T, P>,
     data: &mut D,
-    strategy: S
-) -> Option<()> where T: Types,
+    strategy: S,
+    fuel: Option<usize>,
+    tracer: &mut impl Tracer<T>
+) -> ReduceOutcome<T> where T: Types,
                       D: DataGraph<T>,
                       P: Pattern<T>,
                       M: Mapping<T>,
                       S: Strategy<T>
 
     {
-    let mut strategy = strategy;
-    while let Some(next) = strategy.next_redex(data) {
-        grs.reduce::<D, M>(data, next)?;
-    }
+    reducer::Reducer::<T, D, P, _, M, _>::new(grs, data, strategy)
+        .with_tracer(tracer)
+        .run(fuel)
+}
+
+
+/**
+ * Repeatedly reduce a datagraph until no further steps are indicated,
+ * using the rule chosen by the strategy at each step.
+ *
+ * As with `reduce`, at most `fuel` steps are taken, and the garbage
+ * collector is run between steps whenever the data graph asks for
+ * it. This is likewise a wrapper around `reducer::Reducer`.
+ */
+pub fn reduce_rules<T, D, P, S, M>(
+    grs: &GRS<T, P>,
+    data: &mut D,
+    strategy: S,
+    fuel: Option<usize>,
+    tracer: &mut impl Tracer<T>
+) -> ReduceOutcome<T> where T: Types,
+                            D: DataGraph<T>,
+                            P: Pattern<T>,
+                            M: Mapping<T>,
+                            S: RuleStrategy<T>
+{
+    reducer::Reducer::<T, D, P, _, M, _>::with_rules(grs, data, strategy)
+        .with_tracer(tracer)
+        .run(fuel)
+}
 
-    Some(())
+
+// A failed step ends the run: `NoMatch` means the strategy pointed
+// at a non-redex, anything else is a genuine error.
+fn step_outcome<T: Types, R>(
+    step: Result<R, ReduceError<T>>
+) -> Result<R, ReduceOutcome<T>> {
+    step.map_err(|err| match err {
+        ReduceError::NoMatch(node) => ReduceOutcome::Stuck {node},
+        err => ReduceOutcome::Error(err),
+    })
 }
 
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use std::collections::HashMap;
+    use arena::VecGraph;
+    use trace::NoTrace;
 
     // Demonstration of BYOT (Bring Your Own Types)
     //
@@ -356,19 +1112,36 @@ mod tests {
     // CamelCase or just a single capital letter.
     #[allow(non_camel_case_types)]
     #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-    enum Symbol {a, b, c, d, m, n, o, x, y, z}
+    pub(crate) enum Symbol {a, b, c, d, m, n, o, x, y, z}
 
     // We can get away with a limited set of "constant" values as
     // well.
     #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-    enum Value {Start, Add, If, True, False, Int(i8), Zero, Succ, Hd, Cons}
+    pub(crate) enum Value {Start, Add, If, True, False, Int(i8), Zero, Succ, Hd, Cons, W}
 
     impl SigmaRules for Value {
         type Error = ();
+
+        fn arity(&self) -> Option<usize> {
+            match self {
+                Value::Zero => Some(0),
+                Value::Succ | Value::Hd => Some(1),
+                Value::Add | Value::Cons => Some(2),
+                _ => None,
+            }
+        }
+    }
+
+    impl DenseId for Symbol {
+        fn index(self) -> usize { self as usize }
+        fn from_index(index: usize) -> Option<Self> {
+            use Symbol::*;
+            [a, b, c, d, m, n, o, x, y, z].get(index).copied()
+        }
     }
 
     #[derive(Copy, Clone, Debug, PartialEq)]
-    struct TestTypes;
+    pub(crate) struct TestTypes;
 
     impl Types for TestTypes {
         type Var = Symbol;
@@ -376,44 +1149,14 @@ mod tests {
         type Id  = u8;
     }
 
-    impl<'a> DataGraphBody<'a, TestTypes> for Vec<(Value, Vec<u8>)> {
-        type It = core::iter::Copied<core::slice::Iter<'a, u8>>;
-
-        fn new() -> Self { Vec::new() }
-
-        fn value(&'a self, id: u8) -> Value {
-            self[id as usize].0
-        }
-
-        fn args(&'a self, id: u8) -> Self::It {
-            self[id as usize].1.iter().copied()
-        }
-        fn alloc(&'a mut self, func: Value) -> u8 {
-            self.push((func, Vec::new()));
-            let len = self.len();
-            if len == 256 {
-                panic!("storage exhausted");
-            }
-            (len - 1) as u8
-        }
-
-        fn append_arg(&'a mut self, id: u8, arg: u8) {
-            self[id as usize].1.push(arg);
-        }
-
-        fn redirect(&'a mut self, src: u8, dst: u8) {
-            self.swap(src as usize, dst as usize)
-        }
-
-        fn root(&'a self) -> u8 { 0 }
-    }
+    // For tests which need more nodes than `u8` ids allow.
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub(crate) struct WideTypes;
 
-    impl Mapping<TestTypes> for HashMap<Symbol, u8> {
-        fn new() -> Self { HashMap::new() }
-        fn get(&self, var: Symbol) -> u8 { self[&var] }
-        fn bind(&mut self, var: Symbol, id: u8) {
-            self.insert(var, id);
-        }
+    impl Types for WideTypes {
+        type Var = Symbol;
+        type Val = Value;
+        type Id  = u32;
     }
 
     impl<'a> PatternBody<'a, TestTypes>
@@ -436,7 +1179,474 @@ mod tests {
         fn root(&'a self) -> Symbol { self.1 }
     }
 
+    impl Pattern<TestTypes> for (HashMap<Symbol, (Value, Vec<Symbol>)>, Symbol) {}
+
+    pub(crate) type TestRule = Rule<TestTypes, VecPattern<TestTypes>>;
+    pub(crate) type TestGRS = GRS<TestTypes, VecPattern<TestTypes>>;
+
+    // Shorthand for building rules from canonical form in tests.
+    pub(crate) fn rule(
+        redex: Vec<canonical::Node<Symbol, Value>>,
+        contractum: Vec<canonical::Node<Symbol, Value>>,
+        redirection: (Symbol, Symbol)
+    ) -> Result<TestRule, RuleError<TestTypes>> {
+        TestRule::try_from(canonical::Rule {
+            redex: canonical::Graph(redex),
+            contractum: canonical::Graph(contractum),
+            redirection
+        })
+    }
+
     #[test]
     fn test_grs() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        // r: Hd x, x: Cons a b -> r := a
+        let hd = rule(
+            vec![Node(m, Hd, vec![n]), Node(n, Cons, vec![a, b])],
+            vec![],
+            (m, a)
+        ).unwrap();
+
+        // r: Add x (Succ y) -> Succ (Add x y), r := o
+        let add = rule(
+            vec![Node(m, Add, vec![x, n]), Node(n, Succ, vec![y])],
+            vec![Node(o, Succ, vec![z]), Node(z, Add, vec![x, y])],
+            (m, o)
+        ).unwrap();
+
+        let grs: GRS<TestTypes, VecPattern<TestTypes>> =
+            GRS::new().with_rule(hd).with_rule(add);
+        assert_eq!(grs.len(), 2);
+        assert_eq!(grs.rules()[0].redirection(), (m, a));
+        assert!(grs.rules()[0].contractum().is_none());
+
+        // Start (Hd (Cons (Add Zero (Succ Zero)) Zero))
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let start = g.alloc(Start);
+        let head = g.alloc(Hd);
+        let cons = g.alloc(Cons);
+        let sum = g.alloc(Add);
+        let zero = g.alloc(Zero);
+        let succ = g.alloc(Succ);
+        g.append_arg(start, head);
+        g.append_arg(head, cons);
+        g.append_arg(cons, sum);
+        g.append_arg(cons, zero);
+        g.append_arg(sum, zero);
+        g.append_arg(sum, succ);
+        g.append_arg(succ, zero);
+
+        type M = HashMap<Symbol, u8>;
+        assert_eq!(grs.reduce::<_, M>(&mut g, start, &mut NoTrace), Err(ReduceError::NoMatch(start)));
+        assert_eq!(grs.reduce::<_, M>(&mut g, head, &mut NoTrace), Ok(0));
+        assert_eq!(g.args(start).collect::<Vec<_>>(), vec![sum]);
+
+        assert_eq!(grs.reduce::<_, M>(&mut g, sum, &mut NoTrace), Ok(1));
+        let result = g.args(start).next().unwrap();
+        assert_eq!(g.value(result), Succ);
+        let inner = g.args(result).next().unwrap();
+        assert_eq!(g.value(inner), Add);
+        assert_eq!(g.args(inner).collect::<Vec<_>>(), vec![zero, zero]);
+    }
+
+    #[test]
+    fn test_non_linear() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        // r: Add x x -> Succ x
+        let double = || rule(
+            vec![Node(m, Add, vec![x, x])],
+            vec![Node(n, Succ, vec![x])],
+            (m, n)
+        ).unwrap();
+        let identity = double();
+        let structural = double().with_mode(MatchMode::Structural);
+
+        // Add (Succ Zero) (Succ Zero), with nothing shared.
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let add = g.alloc(Add);
+        let one = g.alloc(Succ);
+        let zero = g.alloc(Zero);
+        let other = g.alloc(Succ);
+        let shared = g.alloc(Add);
+        g.append_arg(add, one);
+        g.append_arg(add, other);
+        g.append_arg(one, zero);
+        g.append_arg(other, zero);
+        g.append_arg(shared, one);
+        g.append_arg(shared, one);
+
+        type M = HashMap<Symbol, u8>;
+        assert!(identity.matches::<M>(&g, add).is_err());
+        assert!(identity.matches::<M>(&g, shared).is_ok());
+        assert!(structural.matches::<M>(&g, add).is_ok());
+        assert!(structural.matches::<M>(&g, shared).is_ok());
+
+        // Add (Succ Zero) (Succ (Succ Zero))
+        let two = g.alloc(Succ);
+        let unequal = g.alloc(Add);
+        g.append_arg(two, one);
+        g.append_arg(unequal, one);
+        g.append_arg(unequal, two);
+        assert!(structural.matches::<M>(&g, unequal).is_err());
+    }
+
+    #[test]
+    fn test_cyclic_pattern() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        // r: Hd x, x: Cons a x -> r := a
+        let ones = rule(
+            vec![Node(m, Hd, vec![x]), Node(x, Cons, vec![a, x])],
+            vec![],
+            (m, a)
+        ).unwrap();
+
+        // r: Hd x, x: Cons a (Cons b x) -> r := b
+        let pairs = rule(
+            vec![
+                Node(m, Hd, vec![x]),
+                Node(x, Cons, vec![a, y]),
+                Node(y, Cons, vec![b, x]),
+            ],
+            vec![],
+            (m, b)
+        ).unwrap();
+
+        // Hd y, y: Cons 1 y
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let hd = g.alloc(Hd);
+        let one = g.alloc(Int(1));
+        let y1 = g.alloc(Cons);
+        g.append_arg(hd, y1);
+        g.append_arg(y1, one);
+        g.append_arg(y1, y1);
+
+        // Hd y, y: Cons 1 (Cons 1 y)
+        let hd2 = g.alloc(Hd);
+        let y2 = g.alloc(Cons);
+        let z2 = g.alloc(Cons);
+        g.append_arg(hd2, y2);
+        g.append_arg(y2, one);
+        g.append_arg(y2, z2);
+        g.append_arg(z2, one);
+        g.append_arg(z2, y2);
+
+        type M = HashMap<Symbol, u8>;
+        assert!(ones.matches::<M>(&g, hd).is_ok());
+        assert!(ones.matches::<M>(&g, hd2).is_err());
+        assert!(pairs.matches::<M>(&g, hd2).is_ok());
+
+        // Matching is a homomorphism, so both pattern nodes may land
+        // on the same data node.
+        assert!(pairs.matches::<M>(&g, hd).is_ok());
+
+        // But a cycle of length two is not one of length one, unless
+        // we only care about structure.
+        let ones = ones.with_mode(MatchMode::Structural);
+        assert!(ones.matches::<M>(&g, hd2).is_ok());
+    }
+
+    #[test]
+    fn test_arity() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        // r: If c x y -> r := x, where If has no declared arity.
+        let pick = rule(vec![Node(m, If, vec![c, x, y])], vec![], (m, x)).unwrap();
+
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let short = g.alloc(If);
+        let long = g.alloc(If);
+        let exact = g.alloc(If);
+        let t = g.alloc(True);
+        for _ in 0..2 {
+            g.append_arg(short, t);
+        }
+        for _ in 0..4 {
+            g.append_arg(long, t);
+        }
+        for _ in 0..3 {
+            g.append_arg(exact, t);
+        }
+
+        type M = HashMap<Symbol, u8>;
+        assert!(pick.matches::<M>(&g, short).is_err());
+        assert!(pick.matches::<M>(&g, long).is_err());
+        assert!(pick.matches::<M>(&g, exact).is_ok());
+
+        // Cons is declared binary.
+        assert_eq!(
+            rule(
+                vec![Node(m, Hd, vec![n]), Node(n, Cons, vec![a])],
+                vec![],
+                (m, a)
+            ).err(),
+            Some(RuleError::Arity {var: n, expected: 2, found: 1})
+        );
+
+        assert_eq!(
+            rule(
+                vec![Node(m, Hd, vec![x])],
+                vec![Node(n, Succ, vec![x, x])],
+                (m, n)
+            ).err(),
+            Some(RuleError::Arity {var: n, expected: 1, found: 2})
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        // r: Add x Zero     -> r := x
+        // r: Add x (Succ y) -> r := y
+        // r: Add x x        -> r := x
+        let grs: GRS<TestTypes, VecPattern<TestTypes>> = GRS::new()
+            .with_rule(rule(
+                vec![Node(m, Add, vec![x, n]), Node(n, Zero, vec![])],
+                vec![],
+                (m, x)
+            ).unwrap())
+            .with_rule(rule(
+                vec![Node(m, Add, vec![x, n]), Node(n, Succ, vec![y])],
+                vec![],
+                (m, y)
+            ).unwrap())
+            .with_rule(rule(vec![Node(m, Add, vec![x, x])], vec![], (m, x)).unwrap());
+
+        // Add True (Cons True True)
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let add = g.alloc(Add);
+        let t = g.alloc(True);
+        let cons = g.alloc(Cons);
+        g.append_arg(add, t);
+        g.append_arg(add, cons);
+        g.append_arg(cons, t);
+        g.append_arg(cons, t);
+
+        let report = grs.explain::<HashMap<Symbol, u8>>(&g, add);
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[0].as_ref().unwrap_err(), &MatchFailure {
+            var: n,
+            node: cons,
+            mismatch: Mismatch::Symbol {expected: Zero, found: Cons}
+        });
+        assert_eq!(report[1].as_ref().unwrap_err(), &MatchFailure {
+            var: n,
+            node: cons,
+            mismatch: Mismatch::Symbol {expected: Succ, found: Cons}
+        });
+        assert_eq!(report[2].as_ref().unwrap_err(), &MatchFailure {
+            var: x,
+            node: cons,
+            mismatch: Mismatch::Bound(t)
+        });
+
+        // Add True (Succ True True) fails on arity instead.
+        g.append_arg(add, t);
+        let report = grs.explain::<HashMap<Symbol, u8>>(&g, add);
+        assert_eq!(report[0].as_ref().unwrap_err(), &MatchFailure {
+            var: m,
+            node: add,
+            mismatch: Mismatch::Arity {expected: 2, found: 3}
+        });
+    }
+
+    #[test]
+    fn test_structurally_equal() {
+        use Value::*;
+
+        // a: Cons Zero a, b: Cons Zero (Cons Zero b), c: Cons Zero Zero
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let zero = g.alloc(Zero);
+        let a = g.alloc(Cons);
+        let b = g.alloc(Cons);
+        let b2 = g.alloc(Cons);
+        let c = g.alloc(Cons);
+        g.append_arg(a, zero);
+        g.append_arg(a, a);
+        g.append_arg(b, zero);
+        g.append_arg(b, b2);
+        g.append_arg(b2, zero);
+        g.append_arg(b2, b);
+        g.append_arg(c, zero);
+        g.append_arg(c, zero);
+
+        assert!(structurally_equal(&g, a, b));
+        assert!(!structurally_equal(&g, a, c));
+        assert!(!structurally_equal(&g, a, zero));
+    }
+
+    #[test]
+    fn test_unbound() {
+        use canonical::{Graph, Node};
+        use Symbol::*;
+        use Value::*;
+
+        let contractum = VecPattern::<TestTypes>::new(Graph(vec![
+            Node(n, Add, vec![x, y]),
+        ])).unwrap();
+
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let zero = g.alloc(Zero);
+        let mut map: HashMap<Symbol, u8> = Mapping::<TestTypes>::new();
+        map.insert(x, zero);
+        let mut fresh: HashMap<Symbol, u8> = Mapping::<TestTypes>::new();
+        assert_eq!(
+            contractum.rewrite(n, &mut g, &map, &mut fresh, &mut NoTrace),
+            Err(ReduceError::Unbound(y))
+        );
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn test_rule_errors() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        assert_eq!(
+            rule(vec![], vec![], (m, a)).err(),
+            Some(RuleError::Redex(PatternError::Empty))
+        );
+
+        assert_eq!(
+            rule(
+                vec![Node(m, Succ, vec![x])],
+                vec![Node(n, Succ, vec![y]), Node(n, Zero, vec![])],
+                (m, n)
+            ).err(),
+            Some(RuleError::Contractum(PatternError::Duplicate(n)))
+        );
+
+        assert_eq!(
+            rule(
+                vec![Node(m, Succ, vec![x])],
+                vec![Node(n, Add, vec![x, y])],
+                (m, n)
+            ).err(),
+            Some(RuleError::UnboundVar(y))
+        );
+
+        assert_eq!(
+            rule(vec![Node(m, Succ, vec![x])], vec![], (n, x)).err(),
+            Some(RuleError::DanglingRedirection(n))
+        );
+
+        assert_eq!(
+            rule(vec![Node(m, Succ, vec![x])], vec![Node(n, Zero, vec![])], (m, o)).err(),
+            Some(RuleError::DanglingRedirection(o))
+        );
+    }
+
+    #[test]
+    fn test_sharing() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        // m: Succ x -> o: Cons z z, z: Succ x. The shared node is
+        // allocated once, and the redirection may target any
+        // contractum node, not just the root.
+        let pair = rule(
+            vec![Node(m, Succ, vec![x])],
+            vec![Node(o, Cons, vec![z, z]), Node(z, Succ, vec![x])],
+            (m, z)
+        ).unwrap();
+
+        // m: Succ x -> o: Cons x o
+        let cycle = rule(
+            vec![Node(m, Succ, vec![x])],
+            vec![Node(o, Cons, vec![x, o])],
+            (m, o)
+        ).unwrap();
+
+        type M = HashMap<Symbol, u8>;
+        let start = || {
+            let mut g: VecGraph<TestTypes> = VecGraph::new();
+            let start = g.alloc(Start);
+            let succ = g.alloc(Succ);
+            let zero = g.alloc(Zero);
+            g.append_arg(start, succ);
+            g.append_arg(succ, zero);
+            g
+        };
+
+        let mut g = start();
+        pair.reduce::<_, M>(&mut g, 1, &mut NoTrace).unwrap();
+        assert_eq!(g.args(3).collect::<Vec<_>>(), vec![4, 4]);
+        assert_eq!(g.args(0).collect::<Vec<_>>(), vec![4]);
+        assert_eq!(g.args(4).collect::<Vec<_>>(), vec![2]);
+
+        let mut g = start();
+        cycle.reduce::<_, M>(&mut g, 1, &mut NoTrace).unwrap();
+        assert_eq!(g.args(3).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(g.args(0).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn test_validate() {
+        use canonical::Node;
+        use Symbol::*;
+        use Value::*;
+
+        let canonical = |redex, contractum, redirection| canonical::Rule {
+            redex: canonical::Graph(redex),
+            contractum: canonical::Graph(contractum),
+            redirection
+        };
+
+        // Every problem is reported, not just the first.
+        let bad = canonical(
+            vec![Node(m, Add, vec![x]), Node(m, Zero, vec![]), Node(m, Zero, vec![])],
+            vec![Node(n, Succ, vec![y]), Node(o, Zero, vec![]), Node(o, Zero, vec![])],
+            (a, n)
+        );
+        assert_eq!(TestRule::validate(&bad), vec![
+            RuleError::Redex(PatternError::Duplicate(m)),
+            RuleError::Contractum(PatternError::Duplicate(o)),
+            RuleError::Unreachable(o),
+            RuleError::Arity {var: m, expected: 2, found: 1},
+            RuleError::UnboundVar(y),
+            RuleError::DanglingRedirection(a),
+        ]);
+        assert_eq!(
+            TestRule::try_from(bad).err(),
+            Some(RuleError::Redex(PatternError::Duplicate(m)))
+        );
+
+        let good = canonical(
+            vec![Node(m, Succ, vec![x])],
+            vec![Node(n, Succ, vec![x])],
+            (m, n)
+        );
+        assert_eq!(TestRule::validate(&good), vec![]);
+
+        let grs = canonical::GRS(vec![
+            good,
+            canonical(vec![], vec![], (m, n)),
+        ]);
+        assert_eq!(
+            TestGRS::validate(&grs),
+            vec![(1, RuleError::Redex(PatternError::Empty))]
+        );
+
+        // A redex whose root is free would match anything.
+        let free = (HashMap::new(), x);
+        assert_eq!(
+            Rule::<TestTypes, _>::new(free, None, (x, x)).err(),
+            Some(RuleError::FreeRoot(x))
+        );
     }
 }
@@ -0,0 +1,743 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides a general-purpose, arena-backed data graph.
+ *
+ * Nodes live in a single `Vec`, and a node's id is simply its index
+ * into that vec. The width of the index is determined by `T::Id`, so
+ * the same implementation serves small graphs with `u8` ids as well
+ * as large ones with `u32` or `usize` ids.
+ *
+ * Redirection is constant-time: the source slot is overwritten with
+ * an indirection to the destination, which `args`, `value` and `root`
+ * follow transparently. Following a chain of indirections points
+ * each of them at the end of the chain, so repeated redirection
+ * doesn't make lookups any slower. The collector shortcuts
+ * indirections, so they never survive a collection.
+ *
+ * Nodes orphaned by reduction are reclaimed by a simple mark-and-sweep
+ * collector. Swept slots go onto a free list, which `alloc` draws from
+ * before growing the arena. For long-running reductions, `compact`
+ * additionally relocates the live nodes to the front of the arena.
+ */
+use core::cell::Cell;
+use core::fmt::{self, Debug, Display};
+use crate::grs::{DataGraph, DataGraphBody, DenseId, Types};
+use crate::grs::traverse::{reachable_from, IdSet};
+use crate::printer::Shorthand;
+
+/**
+ * A single node in the arena.
+ */
+struct Node<T: Types> {
+    value: T::Val,
+    args: Vec<T::Id>,
+}
+
+
+/**
+ * A slot in the arena is either occupied by a node, forwarded to
+ * another slot by a redirection, or on the free list.
+ */
+enum Slot<T: Types> {
+    Live(Node<T>),
+    // A `Cell`, so that `resolve` can compress paths through `&self`.
+    Ind(Cell<T::Id>),
+    Free,
+}
+
+
+/**
+ * Iterator over the arguments of a node, following indirections.
+ */
+pub struct Args<'a, T: Types> where T::Id: DenseId {
+    graph: &'a VecGraph<T>,
+    args: core::slice::Iter<'a, T::Id>,
+}
+
+
+impl<'a, T: Types> Iterator for Args<'a, T> where T::Id: DenseId {
+    type Item = T::Id;
+
+    fn next(&mut self) -> Option<T::Id> {
+        self.args.next().map(|arg| self.graph.resolve(*arg))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.args.size_hint()
+    }
+}
+
+
+/**
+ * Maps ids from before a compaction to ids after it.
+ *
+ * Callers holding ids outside of the graph itself (handles, strategy
+ * worklists, etc) must translate them through this table.
+ */
+pub struct Remap<T: Types> where T::Id: DenseId {
+    forward: Vec<Option<T::Id>>,
+}
+
+
+impl<T: Types> Remap<T> where T::Id: DenseId {
+    /**
+     * The new id of `old`, or `None` if it was not live.
+     */
+    pub fn get(&self, old: T::Id) -> Option<T::Id> {
+        self.forward.get(old.index()).copied().flatten()
+    }
+}
+
+
+impl<T: Types> Debug for Remap<T> where T::Id: DenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.forward.iter().enumerate().filter_map(
+                |(i, new)| new.map(|new| (i, new))
+            ))
+            .finish()
+    }
+}
+
+
+/**
+ * Arena-backed implementation of `DataGraph`.
+ *
+ * The root is the first node allocated, unless set explicitly with
+ * `set_root`.
+ *
+ * The garbage collector treats the root, and any handles registered
+ * with `pin`, as live. Ids of any other unreachable nodes are invalid
+ * after a collection, and may be handed out again by `alloc`.
+ */
+pub struct VecGraph<T: Types> where T::Id: DenseId {
+    slots: Vec<Slot<T>>,
+    free: Vec<T::Id>,
+    pinned: Vec<T::Id>,
+    root: Option<T::Id>,
+    live: usize,
+    allocated: usize,
+    threshold: Option<usize>,
+}
+
+
+impl<T: Types> VecGraph<T> where T::Id: DenseId {
+    /**
+     * Create an empty graph with room for `capacity` nodes.
+     */
+    pub fn with_capacity(capacity: usize) -> Self {
+        VecGraph {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            pinned: Vec::new(),
+            root: None,
+            live: 0,
+            allocated: 0,
+            threshold: None,
+        }
+    }
+
+    /**
+     * The number of live nodes in the arena.
+     */
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /**
+     * Allocate a node, or return `None` if the id space of `T::Id`
+     * has been exhausted.
+     *
+     * Free slots are reused before the arena is grown.
+     */
+    pub fn try_alloc(&mut self, value: T::Val) -> Option<T::Id> {
+        let node = Slot::Live(Node {value, args: Vec::new()});
+        let id = if let Some(id) = self.free.pop() {
+            self.slots[id.index()] = node;
+            id
+        } else {
+            let id = T::Id::from_index(self.slots.len())?;
+            self.slots.push(node);
+            id
+        };
+
+        if self.root.is_none() {
+            self.root = Some(id);
+        }
+        self.live += 1;
+        self.allocated += 1;
+        Some(id)
+    }
+
+    pub fn set_root(&mut self, id: T::Id) {
+        self.root = Some(id);
+    }
+
+    /**
+     * Keep `id` alive across collections, in addition to the root.
+     *
+     * Pins are counted, so a node pinned twice must be unpinned
+     * twice.
+     */
+    pub fn pin(&mut self, id: T::Id) {
+        self.pinned.push(id);
+    }
+
+    /**
+     * Release one pin on `id`. Pins follow redirection, so a node
+     * pinned before it was redirected is unpinned by its new id.
+     */
+    pub fn unpin(&mut self, id: T::Id) {
+        let id = self.resolve(id);
+        if let Some(i) = self.pinned.iter().position(|p| self.resolve(*p) == id) {
+            self.pinned.swap_remove(i);
+        }
+    }
+
+    /**
+     * Request a collection once `threshold` nodes have been allocated
+     * since the last one. `None` disables automatic collection.
+     *
+     * See `DataGraphBody::wants_gc`.
+     */
+    pub fn set_gc_threshold(&mut self, threshold: Option<usize>) {
+        self.threshold = threshold;
+    }
+
+    /**
+     * Follow indirections from `id` to the live node it stands for,
+     * then point every indirection on the way directly at that node.
+     */
+    fn resolve(&self, id: T::Id) -> T::Id {
+        let mut end = id;
+        while let Slot::Ind(next) = &self.slots[end.index()] {
+            end = next.get();
+        }
+
+        let mut id = id;
+        while let Slot::Ind(next) = &self.slots[id.index()] {
+            id = next.replace(end);
+        }
+        end
+    }
+
+    /**
+     * Rewrite every id held by the graph to skip indirections, so
+     * that no live node, pin or root refers to an indirection slot.
+     */
+    fn shortcut(&mut self) {
+        for i in 0..self.slots.len() {
+            let args = match &mut self.slots[i] {
+                Slot::Live(node) => core::mem::take(&mut node.args),
+                _ => continue,
+            };
+            let args = args.into_iter().map(|arg| self.resolve(arg)).collect();
+            if let Slot::Live(node) = &mut self.slots[i] {
+                node.args = args;
+            }
+        }
+
+        self.root = self.root.map(|root| self.resolve(root));
+        for i in 0..self.pinned.len() {
+            self.pinned[i] = self.resolve(self.pinned[i]);
+        }
+    }
+
+    /**
+     * Free every slot which was not marked, returning the number of
+     * nodes reclaimed. Indirections are never marked, so they are
+     * always freed, but don't count as nodes.
+     */
+    fn sweep(&mut self, marks: &IdSet<T::Id>) -> usize {
+        let mut freed = 0;
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            let live = match slot {
+                Slot::Live(_) => true,
+                Slot::Ind(_) => false,
+                Slot::Free => continue,
+            };
+            // Every slot index was a valid id when allocated.
+            let id = T::Id::from_index(i).unwrap();
+            if !marks.contains(id) {
+                *slot = Slot::Free;
+                self.free.push(id);
+                if live {
+                    freed += 1;
+                }
+            }
+        }
+        self.live -= freed;
+        freed
+    }
+
+    /**
+     * Run a Cheney-style copying collection.
+     *
+     * Live nodes are relocated contiguously, in breadth-first order
+     * from the root and then the pinned handles, and every argument
+     * id is rewritten. The root and the pins are updated in place;
+     * any other ids held by the caller must be translated through the
+     * returned table.
+     *
+     * Indirections are not copied. The table maps a redirected id to
+     * the new id of the node it was redirected to.
+     */
+    pub fn compact(&mut self) -> Remap<T> {
+        self.shortcut();
+        let mut from = core::mem::take(&mut self.slots);
+        let mut forward: Vec<Option<T::Id>> = vec![None; from.len()];
+
+        // Evacuate a node from the old arena, unless it has already
+        // been copied, and return its new id.
+        let mut copy = |to: &mut Vec<Slot<T>>, old: T::Id| -> T::Id {
+            if let Some(new) = forward[old.index()] {
+                return new;
+            }
+            // The new arena is never larger than the old one.
+            let new = T::Id::from_index(to.len()).unwrap();
+            match core::mem::replace(&mut from[old.index()], Slot::Free) {
+                Slot::Live(node) => to.push(Slot::Live(node)),
+                // After shortcutting, nothing refers to an indirection.
+                Slot::Ind(_) => unreachable!(),
+                Slot::Free => panic!("dangling id {:?}", old),
+            }
+            forward[old.index()] = Some(new);
+            new
+        };
+
+        let mut to = Vec::with_capacity(self.live);
+        self.root = self.root.map(|root| copy(&mut to, root));
+        for pin in self.pinned.iter_mut() {
+            *pin = copy(&mut to, *pin);
+        }
+
+        let mut scan = 0;
+        while scan < to.len() {
+            let mut args = match &mut to[scan] {
+                Slot::Live(node) => core::mem::take(&mut node.args),
+                _ => unreachable!(),
+            };
+            for arg in args.iter_mut() {
+                *arg = copy(&mut to, *arg);
+            }
+            if let Slot::Live(node) = &mut to[scan] {
+                node.args = args;
+            }
+            scan += 1;
+        }
+
+        // Indirections remain in the old arena, so they can be
+        // followed to the new id of their target.
+        for i in 0..from.len() {
+            let mut target = i;
+            while let Slot::Ind(next) = &from[target] {
+                target = next.get().index();
+            }
+            if target != i {
+                forward[i] = forward[target];
+            }
+        }
+
+        self.live = to.len();
+        self.slots = to;
+        self.free.clear();
+        self.allocated = 0;
+        Remap {forward}
+    }
+
+    fn node(&self, id: T::Id) -> &Node<T> {
+        match &self.slots[self.resolve(id).index()] {
+            Slot::Live(node) => node,
+            _ => panic!("dangling id {:?}", id),
+        }
+    }
+
+    fn node_mut(&mut self, id: T::Id) -> &mut Node<T> {
+        let resolved = self.resolve(id);
+        match &mut self.slots[resolved.index()] {
+            Slot::Live(node) => node,
+            _ => panic!("dangling id {:?}", id),
+        }
+    }
+}
+
+
+// Marking goes through `traverse`, which needs `VecGraph` to be a
+// `DataGraph`, and so `T: 'static`.
+impl<T: Types + 'static> VecGraph<T> where T::Id: DenseId {
+    /**
+     * Mark every node reachable from the root and the pinned handles.
+     */
+    fn mark(&self) -> IdSet<T::Id> {
+        reachable_from(self, self.pinned.iter().copied().chain(self.root))
+    }
+
+    /**
+     * Run a full mark-and-sweep collection, returning the number of
+     * nodes reclaimed.
+     *
+     * Ids of redirected nodes are invalid after a collection.
+     */
+    pub fn collect(&mut self) -> usize {
+        self.shortcut();
+        let marks = self.mark();
+        self.allocated = 0;
+        self.sweep(&marks)
+    }
+}
+
+
+impl<T: Types> Default for VecGraph<T> where T::Id: DenseId {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+
+// Renders the graph in shorthand notation. See `printer`.
+impl<T: Types + 'static> Display for VecGraph<T> where T::Id: DenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            Shorthand::empty(self).fmt(f)
+        } else {
+            Shorthand::new(self).fmt(f)
+        }
+    }
+}
+
+
+impl<T: Types> Debug for VecGraph<T> where T::Id: DenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.slots.iter().enumerate().filter_map(
+                |(i, slot)| match slot {
+                    Slot::Live(node) => Some((i, (node.value, &node.args))),
+                    _ => None,
+                }
+            ))
+            .finish()
+    }
+}
+
+
+// Types are zero-sized markers in practice, so requiring `T: 'static`
+// costs nothing, and lets `Args` borrow the arena for any `'a`.
+impl<'a, T: Types + 'static> DataGraphBody<'a, T> for VecGraph<T> where T::Id: DenseId {
+    type It = Args<'a, T>;
+
+    fn new() -> Self { Self::default() }
+
+    fn args(&'a self, id: T::Id) -> Self::It {
+        Args {graph: self, args: self.node(id).args.iter()}
+    }
+
+    fn value(&'a self, id: T::Id) -> T::Val {
+        self.node(id).value
+    }
+
+    fn alloc(&'a mut self, func: T::Val) -> T::Id {
+        self.try_alloc(func).expect("storage exhausted")
+    }
+
+    fn append_arg(&'a mut self, id: T::Id, arg: T::Id) {
+        self.node_mut(id).args.push(arg);
+    }
+
+    /**
+     * Every reference to `src` is replaced with a reference to
+     * `dst`, including the root.
+     *
+     * The node at `src` is overwritten with an indirection to `dst`,
+     * so this takes constant time. Its contents are dropped, and its
+     * arguments are left for the collector.
+     */
+    fn redirect(&'a mut self, src: T::Id, dst: T::Id) {
+        let (src, dst) = (self.resolve(src), self.resolve(dst));
+        if src != dst {
+            self.slots[src.index()] = Slot::Ind(Cell::new(dst));
+            self.live -= 1;
+        }
+    }
+
+    fn root(&'a self) -> T::Id {
+        self.resolve(self.root.expect("graph has no root"))
+    }
+
+    fn gc(&'a mut self) {
+        self.collect();
+    }
+
+    fn wants_gc(&'a self) -> bool {
+        match self.threshold {
+            Some(threshold) => self.allocated >= threshold,
+            None => false,
+        }
+    }
+}
+
+
+impl<T: Types + 'static> DataGraph<T> for VecGraph<T> where T::Id: DenseId {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::tests::{TestTypes, Value, WideTypes};
+    use Value::*;
+
+    #[test]
+    fn test_alloc() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let hd = g.alloc(Hd);
+        let cons = g.alloc(Cons);
+        let one = g.alloc(Int(1));
+        let nil = g.alloc(Zero);
+        g.append_arg(hd, cons);
+        g.append_arg(cons, one);
+        g.append_arg(cons, nil);
+
+        assert_eq!(g.root(), hd);
+        assert_eq!(g.value(cons), Cons);
+        assert_eq!(g.args(hd).collect::<Vec<_>>(), vec![cons]);
+        assert_eq!(g.args(cons).collect::<Vec<_>>(), vec![one, nil]);
+        assert_eq!(g.args(one).count(), 0);
+        assert_eq!(g.to_string(), "Hd (Cons Int(1) Zero)");
+        assert_eq!(VecGraph::<TestTypes>::new().to_string(), "_");
+    }
+
+    #[test]
+    fn test_redirect() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let add = g.alloc(Add);
+        let x = g.alloc(Int(1));
+        let y = g.alloc(Int(2));
+        g.append_arg(add, x);
+        g.append_arg(add, x);
+
+        g.redirect(x, y);
+        assert_eq!(g.args(add).collect::<Vec<_>>(), vec![y, y]);
+
+        g.redirect(add, y);
+        assert_eq!(g.root(), y);
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn test_indirection() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let start = g.alloc(Start);
+        let a = g.alloc(Succ);
+        let b = g.alloc(Succ);
+        let zero = g.alloc(Zero);
+        g.append_arg(start, a);
+        g.append_arg(a, b);
+        g.append_arg(b, zero);
+        g.pin(a);
+
+        // Chains of indirections are followed, and redirecting a
+        // node to itself is a no-op.
+        g.redirect(a, b);
+        g.redirect(b, zero);
+        g.redirect(a, zero);
+        assert_eq!(g.args(start).collect::<Vec<_>>(), vec![zero]);
+        assert_eq!(g.value(a), Zero);
+        assert_eq!(g.len(), 2);
+
+        // Collection shortcuts the indirections and frees their
+        // slots, keeping the pinned node alive under its new id.
+        assert_eq!(g.collect(), 0);
+        assert_eq!(g.args(start).collect::<Vec<_>>(), vec![zero]);
+        assert_eq!(g.alloc(Zero), b);
+        assert_eq!(g.alloc(Zero), a);
+        g.unpin(zero);
+        assert_eq!(g.collect(), 2);
+
+        // A pin can be released by the redirected-to id before any
+        // collection has shortcut it.
+        let c = g.alloc(Succ);
+        let d = g.alloc(Zero);
+        g.pin(c);
+        g.redirect(c, d);
+        g.unpin(d);
+        assert_eq!(g.collect(), 1);
+        assert_eq!(g.len(), 2);
+    }
+
+    #[test]
+    fn test_indirection_chain() {
+        // Each redirection targets a node which is redirected in turn,
+        // as when reducing `W -> W` without collection. Without path
+        // compression, every lookup of the root would walk the whole
+        // chain.
+        const STEPS: u32 = 100_000;
+        let mut g: VecGraph<WideTypes> = VecGraph::new();
+        let first = g.alloc(W);
+        let mut last = first;
+        for _ in 0..STEPS {
+            let next = g.alloc(W);
+            g.redirect(last, next);
+            assert_eq!(g.root(), next);
+            last = next;
+        }
+        assert_eq!(g.len(), 1);
+        assert_eq!(g.value(first), W);
+        assert_eq!(g.collect(), 0);
+        assert_eq!(g.root(), last);
+    }
+
+    #[test]
+    fn test_compact_indirection() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let start = g.alloc(Start);
+        let old = g.alloc(Succ);
+        let new = g.alloc(Zero);
+        g.append_arg(start, old);
+        g.append_arg(old, new);
+        g.redirect(old, new);
+
+        let remap = g.compact();
+        assert_eq!(g.len(), 2);
+        assert_eq!(remap.get(old), remap.get(new));
+        assert_eq!(g.args(g.root()).collect::<Vec<_>>(), vec![remap.get(new).unwrap()]);
+    }
+
+    #[test]
+    fn test_exhausted() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        for _ in 0..256 {
+            assert!(g.try_alloc(Zero).is_some());
+        }
+        assert_eq!(g.try_alloc(Zero), None);
+
+        let mut g: VecGraph<WideTypes> = VecGraph::new();
+        for _ in 0..1000 {
+            g.alloc(Zero);
+        }
+        assert_eq!(g.len(), 1000);
+    }
+
+    #[test]
+    fn test_gc() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let cons = g.alloc(Cons);
+        let hd = g.alloc(Int(1));
+        let tl = g.alloc(Int(2));
+        let orphan = g.alloc(Succ);
+        let pinned = g.alloc(Zero);
+        g.append_arg(cons, hd);
+        g.append_arg(cons, tl);
+        g.append_arg(orphan, tl);
+        g.pin(pinned);
+
+        assert_eq!(g.collect(), 1);
+        assert_eq!(g.len(), 4);
+        assert_eq!(g.args(cons).collect::<Vec<_>>(), vec![hd, tl]);
+
+        // The swept slot is reused before the arena grows.
+        assert_eq!(g.alloc(Start), orphan);
+
+        g.unpin(pinned);
+        assert_eq!(g.collect(), 2);
+        assert_eq!(g.alloc(True), pinned);
+    }
+
+    #[test]
+    fn test_gc_cycle() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let root = g.alloc(Start);
+        let a = g.alloc(Cons);
+        let b = g.alloc(Cons);
+        g.append_arg(a, b);
+        g.append_arg(b, a);
+        g.append_arg(root, a);
+        assert_eq!(g.collect(), 0);
+
+        let c = g.alloc(If);
+        g.set_root(c);
+        assert_eq!(g.collect(), 3);
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let garbage = g.alloc(Zero);
+        let cons = g.alloc(Cons);
+        let hd = g.alloc(Int(1));
+        let more = g.alloc(Zero);
+        let tl = g.alloc(Int(2));
+        let pinned = g.alloc(Succ);
+        g.append_arg(cons, hd);
+        g.append_arg(cons, tl);
+        g.append_arg(tl, cons);
+        g.append_arg(pinned, tl);
+        g.set_root(cons);
+        g.pin(pinned);
+
+        let remap = g.compact();
+        assert_eq!(g.len(), 4);
+        assert_eq!(remap.get(garbage), None);
+        assert_eq!(remap.get(more), None);
+        assert_eq!(remap.get(cons), Some(0));
+        assert_eq!(remap.get(pinned), Some(1));
+
+        let cons = g.root();
+        let (hd, tl) = (remap.get(hd).unwrap(), remap.get(tl).unwrap());
+        assert_eq!(g.value(hd), Int(1));
+        assert_eq!(g.args(cons).collect::<Vec<_>>(), vec![hd, tl]);
+        assert_eq!(g.args(tl).collect::<Vec<_>>(), vec![cons]);
+        assert_eq!(g.args(1).collect::<Vec<_>>(), vec![tl]);
+
+        // The arena is now dense, so allocation grows it from the end.
+        assert_eq!(g.alloc(Zero), 4);
+
+        // Unpinning works with the relocated id.
+        g.unpin(1);
+        assert_eq!(g.collect(), 2);
+    }
+
+    #[test]
+    fn test_gc_threshold() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        g.alloc(Start);
+        assert!(!g.wants_gc());
+
+        g.set_gc_threshold(Some(3));
+        g.alloc(Zero);
+        g.alloc(Zero);
+        assert!(g.wants_gc());
+        g.gc();
+        assert!(!g.wants_gc());
+        assert_eq!(g.len(), 1);
+    }
+}
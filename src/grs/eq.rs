@@ -0,0 +1,206 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides equality of rooted data graphs.
+ *
+ * Node ids are an artifact of allocation order, so two runs of the
+ * same reduction can produce the same graph under different ids, or
+ * in different `DataGraph` implementations. The comparisons here look
+ * only at what is reachable from the given roots.
+ *
+ * `isomorphic` respects sharing: a node shared in one graph must be
+ * shared in the other. `bisimilar` ignores sharing, so that, for
+ * example, `Cons x x` with `x` shared is equal to `Cons (Zero)
+ * (Zero)` with two distinct copies, and a cycle is equal to any
+ * unrolling of it.
+ */
+use crate::grs::{DataGraph, DenseId, Types};
+
+
+/**
+ * Returns true if the subgraphs reachable from `root_a` in `a` and
+ * `root_b` in `b` are identical up to renaming of ids.
+ *
+ * Since arguments are ordered, there is at most one candidate
+ * renaming, and this runs in linear time.
+ */
+pub fn isomorphic<T, A, B>(a: &A, root_a: T::Id, b: &B, root_b: T::Id) -> bool
+where T: Types, T::Id: DenseId, A: DataGraph<T>, B: DataGraph<T>
+{
+    // The renaming being built, in both directions, by index.
+    let mut forward: Vec<Option<T::Id>> = Vec::new();
+    let mut backward: Vec<Option<T::Id>> = Vec::new();
+    let mut stack = vec![(root_a, root_b)];
+
+    while let Some((x, y)) = stack.pop() {
+        match (lookup(&forward, x), lookup(&backward, y)) {
+            (Some(fx), Some(by)) if fx == y && by == x => continue,
+            (None, None) => (),
+            _ => return false,
+        }
+        assign(&mut forward, x, y);
+        assign(&mut backward, y, x);
+
+        if a.value(x) != b.value(y) || a.args(x).count() != b.args(y).count() {
+            return false;
+        }
+        stack.extend(a.args(x).zip(b.args(y)));
+    }
+
+    true
+}
+
+
+/**
+ * Returns true if the subgraphs reachable from `root_a` in `a` and
+ * `root_b` in `b` unfold to the same, possibly infinite, tree.
+ *
+ * Cycles are handled coinductively: a pair of nodes already under
+ * comparison is assumed to be equal.
+ *
+ * `structurally_equal` is this comparison within a single graph.
+ */
+pub fn bisimilar<T, A, B>(a: &A, root_a: T::Id, b: &B, root_b: T::Id) -> bool
+where T: Types, T::Id: DenseId, A: DataGraph<T>, B: DataGraph<T>
+{
+    // The pairs assumed equal, as the partners of each node of `a`.
+    // A node rarely has more than one, so each row is kept short.
+    let mut assumed: Vec<Vec<T::Id>> = Vec::new();
+    bisimulate(a, root_a, b, root_b, |x, y| {
+        let i = x.index();
+        if i >= assumed.len() {
+            assumed.resize(i + 1, Vec::new());
+        }
+        if assumed[i].contains(&y) {
+            false
+        } else {
+            assumed[i].push(y);
+            true
+        }
+    })
+}
+
+
+/**
+ * The walk behind `bisimilar` and `structurally_equal`.
+ *
+ * `assume` is called on each pair of nodes reached, and returns false
+ * if the pair need not be compared, usually because it already is.
+ */
+pub(crate) fn bisimulate<T, A, B>(
+    a: &A,
+    root_a: T::Id,
+    b: &B,
+    root_b: T::Id,
+    mut assume: impl FnMut(T::Id, T::Id) -> bool,
+) -> bool
+where T: Types, A: DataGraph<T>, B: DataGraph<T>
+{
+    let mut stack = vec![(root_a, root_b)];
+
+    while let Some((x, y)) = stack.pop() {
+        if !assume(x, y) {
+            continue;
+        }
+
+        if a.value(x) != b.value(y) || a.args(x).count() != b.args(y).count() {
+            return false;
+        }
+        stack.extend(a.args(x).zip(b.args(y)));
+    }
+
+    true
+}
+
+
+fn lookup<I: DenseId>(map: &[Option<I>], id: I) -> Option<I> {
+    map.get(id.index()).copied().flatten()
+}
+
+
+fn assign<I: DenseId>(map: &mut Vec<Option<I>>, from: I, to: I) {
+    let i = from.index();
+    if i >= map.len() {
+        map.resize(i + 1, None);
+    }
+    map[i] = Some(to);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::DataGraphBody;
+    use crate::grs::rc::RcGraph;
+    use crate::grs::strategy::tests::graph;
+    use crate::grs::tests::{TestTypes, Value};
+    use Value::*;
+
+    #[test]
+    fn test_isomorphic() {
+        // Cons x x, x: Succ Zero, with ids in a different order, and
+        // some unreachable garbage.
+        let a = graph(&[(Cons, &[1, 1]), (Succ, &[2]), (Zero, &[])]);
+        let b = graph(&[(Zero, &[]), (Cons, &[3, 3]), (Add, &[]), (Succ, &[0])]);
+        assert!(isomorphic(&a, 0, &b, 1));
+
+        // The same graph in a different implementation.
+        let mut c: RcGraph<TestTypes> = RcGraph::new();
+        let cons = c.alloc(Cons);
+        let succ = c.alloc(Succ);
+        let zero = c.alloc(Zero);
+        c.append_arg(cons, succ);
+        c.append_arg(cons, succ);
+        c.append_arg(succ, zero);
+        assert!(isomorphic(&a, 0, &c, cons));
+
+        // Unshared copies are not isomorphic, but are bisimilar.
+        let d = graph(&[(Cons, &[1, 3]), (Succ, &[2]), (Zero, &[]), (Succ, &[2])]);
+        assert!(!isomorphic(&a, 0, &d, 0));
+        assert!(!isomorphic(&d, 0, &a, 0));
+        assert!(bisimilar(&a, 0, &d, 0));
+
+        let e = graph(&[(Cons, &[1, 1]), (Succ, &[2]), (Succ, &[])]);
+        assert!(!isomorphic(&a, 0, &e, 0));
+        assert!(!bisimilar(&a, 0, &e, 0));
+    }
+
+    #[test]
+    fn test_cycles() {
+        // x: Cons Zero x, against its unrolling y: Cons Zero (Cons Zero y).
+        let a = graph(&[(Cons, &[1, 0]), (Zero, &[])]);
+        let b = graph(&[(Cons, &[1, 2]), (Zero, &[]), (Cons, &[1, 0])]);
+        assert!(isomorphic(&a, 0, &a, 0));
+        assert!(!isomorphic(&a, 0, &b, 0));
+        assert!(bisimilar(&a, 0, &b, 0));
+        assert!(bisimilar(&b, 0, &a, 0));
+
+        let c = graph(&[(Cons, &[1, 2]), (Zero, &[]), (Cons, &[0, 0])]);
+        assert!(!bisimilar(&a, 0, &c, 0));
+    }
+}
@@ -0,0 +1,209 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides implementations of `Mapping`.
+ *
+ * - `SlotMapping`, a dense array indexed by var, for when vars are
+ *   small integers.
+ * - `LinearMapping`, a fixed-capacity list searched linearly, for
+ *   tiny patterns. It only requires `T::Var: PartialEq`.
+ * - `HashMap`, for everything else.
+ *
+ * The first two never allocate.
+ */
+use core::fmt::{self, Debug};
+use core::hash::Hash;
+use std::collections::HashMap;
+use crate::grs::{DenseId, Mapping, Types};
+
+
+/**
+ * Mapping backed by an array of `N` slots, indexed by var.
+ *
+ * Binding a var whose index is `N` or greater panics.
+ */
+pub struct SlotMapping<T: Types, const N: usize> where T::Var: DenseId {
+    slots: [Option<T::Id>; N],
+}
+
+
+impl<T: Types, const N: usize> Debug for SlotMapping<T, N> where T::Var: DenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.slots.iter().enumerate().filter_map(
+                |(i, id)| id.map(|id| (i, id))
+            ))
+            .finish()
+    }
+}
+
+
+impl<T: Types, const N: usize> Mapping<T> for SlotMapping<T, N> where T::Var: DenseId {
+    fn new() -> Self {
+        SlotMapping {slots: [None; N]}
+    }
+
+    fn try_get(&self, var: T::Var) -> Option<T::Id> {
+        self.slots.get(var.index()).copied().flatten()
+    }
+
+    fn bind(&mut self, var: T::Var, id: T::Id) {
+        match self.slots.get_mut(var.index()) {
+            Some(slot) => *slot = Some(id),
+            None => panic!("{:?} exceeds mapping capacity {}", var, N),
+        }
+    }
+}
+
+
+/**
+ * Mapping backed by an array of up to `N` bindings, searched
+ * linearly.
+ *
+ * Binding more than `N` distinct vars panics.
+ */
+pub struct LinearMapping<T: Types, const N: usize> {
+    bindings: [Option<(T::Var, T::Id)>; N],
+    len: usize,
+}
+
+
+impl<T: Types, const N: usize> LinearMapping<T, N> {
+    fn bound(&self) -> &[Option<(T::Var, T::Id)>] {
+        &self.bindings[..self.len]
+    }
+}
+
+
+impl<T: Types, const N: usize> Debug for LinearMapping<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.bound().iter().flatten().map(|(var, id)| (var, id)))
+            .finish()
+    }
+}
+
+
+impl<T: Types, const N: usize> Mapping<T> for LinearMapping<T, N> {
+    fn new() -> Self {
+        LinearMapping {bindings: [None; N], len: 0}
+    }
+
+    fn try_get(&self, var: T::Var) -> Option<T::Id> {
+        self.bound().iter().flatten()
+            .find(|(v, _)| *v == var)
+            .map(|(_, id)| *id)
+    }
+
+    fn bind(&mut self, var: T::Var, id: T::Id) {
+        let len = self.len;
+        for binding in self.bindings[..len].iter_mut().flatten() {
+            if binding.0 == var {
+                binding.1 = id;
+                return;
+            }
+        }
+
+        if len == N {
+            panic!("{:?} exceeds mapping capacity {}", var, N);
+        }
+        self.bindings[len] = Some((var, id));
+        self.len += 1;
+    }
+}
+
+
+impl<T: Types> Mapping<T> for HashMap<T::Var, T::Id> where T::Var: Hash + Eq {
+    fn new() -> Self {
+        HashMap::new()
+    }
+
+    fn try_get(&self, var: T::Var) -> Option<T::Id> {
+        self.get(&var).copied()
+    }
+
+    fn bind(&mut self, var: T::Var, id: T::Id) {
+        self.insert(var, id);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::tests::{Symbol, TestTypes};
+    use Symbol::*;
+
+    fn exercise<M: Mapping<TestTypes>>() -> M {
+        let mut map = M::new();
+        assert_eq!(map.try_get(x), None);
+        map.bind(x, 1);
+        map.bind(y, 2);
+        map.bind(x, 3);
+        assert_eq!(map.try_get(x), Some(3));
+        assert_eq!(map.get(y), 2);
+        assert_eq!(map.try_get(z), None);
+        map
+    }
+
+    #[test]
+    fn test_slot_mapping() {
+        exercise::<SlotMapping<TestTypes, 10>>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slot_mapping_capacity() {
+        let mut map: SlotMapping<TestTypes, 2> = Mapping::new();
+        map.bind(z, 0);
+    }
+
+    #[test]
+    fn test_linear_mapping() {
+        let map = exercise::<LinearMapping<TestTypes, 2>>();
+        assert_eq!(map.len, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_linear_mapping_capacity() {
+        exercise::<LinearMapping<TestTypes, 1>>();
+    }
+
+    #[test]
+    fn test_hash_mapping() {
+        exercise::<HashMap<Symbol, u8>>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unbound() {
+        let map: HashMap<Symbol, u8> = Mapping::<TestTypes>::new();
+        Mapping::<TestTypes>::get(&map, x);
+    }
+}
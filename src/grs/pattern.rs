@@ -0,0 +1,260 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides a general-purpose pattern representation.
+ *
+ * Patterns are small, so nodes are stored in a flat `Vec` and looked
+ * up by linear search. This only requires `T::Var: PartialEq`, and
+ * in practice beats hashing for the handful of nodes in a typical
+ * redex or contractum.
+ */
+use core::fmt::{self, Debug};
+use crate::ast::canonical;
+use crate::grs::{Pattern, PatternBody, Types};
+
+
+struct Node<T: Types> {
+    var: T::Var,
+    value: T::Val,
+    args: Vec<T::Var>,
+}
+
+
+/**
+ * Reasons a canonical graph can't be used as a pattern.
+ */
+#[derive(Debug, PartialEq)]
+pub enum PatternError<T: Types> {
+    // The graph has no nodes, so there is no root.
+    Empty,
+    // The same var labels more than one node.
+    Duplicate(T::Var),
+}
+
+
+/**
+ * Vec-backed implementation of `Pattern`.
+ *
+ * Any var which is referenced as an argument, but which doesn't
+ * label a node, is a free variable of the pattern.
+ */
+pub struct VecPattern<T: Types> {
+    nodes: Vec<Node<T>>,
+}
+
+
+impl<T: Types> VecPattern<T> {
+    /**
+     * Build a pattern from its canonical form, whose first node is
+     * the root.
+     */
+    pub fn new(graph: canonical::Pattern<T>) -> Result<Self, PatternError<T>> {
+        if let Some(err) = Self::validate(&graph).into_iter().next() {
+            return Err(err);
+        }
+
+        let nodes = graph.0
+            .into_iter()
+            .map(|canonical::Node(var, value, args)| Node {var, value, args})
+            .collect();
+
+        Ok(VecPattern {nodes})
+    }
+
+    /**
+     * Report every reason `graph` can't be used as a pattern. Each
+     * duplicated var is reported once.
+     */
+    pub fn validate(graph: &canonical::Pattern<T>) -> Vec<PatternError<T>> {
+        if graph.0.is_empty() {
+            return vec![PatternError::Empty];
+        }
+
+        let mut errors = Vec::new();
+        for (i, canonical::Node(var, _, _)) in graph.0.iter().enumerate() {
+            let seen = graph.0[..i].iter().filter(|node| node.0 == *var).count();
+            if seen == 1 {
+                errors.push(PatternError::Duplicate(*var));
+            }
+        }
+        errors
+    }
+
+    /**
+     * Build a pattern from a canonical graph without validating it.
+     *
+     * Where a var labels more than one node, the first one wins. The
+     * graph must not be empty.
+     */
+    pub(crate) fn from_canonical(graph: &canonical::Pattern<T>) -> Self {
+        let mut nodes: Vec<Node<T>> = Vec::with_capacity(graph.0.len());
+        for canonical::Node(var, value, args) in graph.0.iter() {
+            if !nodes.iter().any(|node| node.var == *var) {
+                nodes.push(Node {var: *var, value: *value, args: args.clone()});
+            }
+        }
+        VecPattern {nodes}
+    }
+
+    /**
+     * The vars which label nodes in this pattern, root first.
+     */
+    pub fn vars(&self) -> impl Iterator<Item = T::Var> + '_ {
+        self.nodes.iter().map(|node| node.var)
+    }
+
+    /**
+     * The free variables of this pattern, in order of first
+     * occurrence.
+     */
+    pub fn free_vars(&self) -> Vec<T::Var> {
+        let mut free: Vec<T::Var> = Vec::new();
+        for var in self.nodes.iter().flat_map(|node| node.args.iter()) {
+            if self.find(*var).is_none() && !free.contains(var) {
+                free.push(*var);
+            }
+        }
+        free
+    }
+
+    fn find(&self, var: T::Var) -> Option<&Node<T>> {
+        self.nodes.iter().find(|node| node.var == var)
+    }
+
+    fn node(&self, var: T::Var) -> &Node<T> {
+        self.find(var).unwrap_or_else(|| panic!("{:?} is a free var", var))
+    }
+}
+
+
+impl<T: Types> Debug for VecPattern<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.nodes.iter().map(
+                |node| (node.var, (node.value, &node.args))
+            ))
+            .finish()
+    }
+}
+
+
+impl<'a, T: Types> PatternBody<'a, T> for VecPattern<T> where T::Var: 'static {
+    type It = core::iter::Copied<core::slice::Iter<'a, T::Var>>;
+
+    fn contains(&'a self, id: T::Var) -> bool {
+        self.find(id).is_some()
+    }
+
+    fn value(&'a self, id: T::Var) -> T::Val {
+        self.node(id).value
+    }
+
+    fn args(&'a self, id: T::Var) -> Self::It {
+        self.node(id).args.iter().copied()
+    }
+
+    fn root(&'a self) -> T::Var {
+        self.nodes[0].var
+    }
+}
+
+
+impl<T: Types> Pattern<T> for VecPattern<T> where T::Var: 'static {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::ast::canonical::{Graph, Node};
+    use crate::grs::{DataGraphBody, MatchMode};
+    use crate::grs::trace::NoTrace;
+    use crate::grs::arena::VecGraph;
+    use crate::grs::tests::{Symbol, TestTypes, Value};
+    use Symbol::*;
+    use Value::*;
+
+    // Hd (Cons a b)
+    fn hd() -> VecPattern<TestTypes> {
+        VecPattern::new(Graph(vec![
+            Node(x, Hd, vec![y]),
+            Node(y, Cons, vec![a, b]),
+        ])).unwrap()
+    }
+
+    #[test]
+    fn test_pattern() {
+        let p = hd();
+        assert_eq!(p.root(), x);
+        assert!(p.contains(y));
+        assert!(!p.contains(a));
+        assert_eq!(p.value(y), Cons);
+        assert_eq!(p.args(y).collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(p.vars().collect::<Vec<_>>(), vec![x, y]);
+        assert_eq!(p.free_vars(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            VecPattern::<TestTypes>::new(Graph(vec![])).unwrap_err(),
+            PatternError::Empty
+        );
+
+        assert_eq!(
+            VecPattern::<TestTypes>::new(Graph(vec![
+                Node(x, Hd, vec![y]),
+                Node(y, Cons, vec![a, b]),
+                Node(x, Zero, vec![]),
+            ])).unwrap_err(),
+            PatternError::Duplicate(x)
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut g: VecGraph<TestTypes> = VecGraph::new();
+        let node = g.alloc(Hd);
+        let cons = g.alloc(Cons);
+        let one = g.alloc(Int(1));
+        let zero = g.alloc(Zero);
+        g.append_arg(node, cons);
+        g.append_arg(cons, one);
+        g.append_arg(cons, zero);
+
+        let p = hd();
+        let mut map: HashMap<Symbol, u8> = HashMap::new();
+        assert_eq!(p.matches(x, &g, node, &mut map, MatchMode::Identity, &mut NoTrace), Ok(()));
+        assert_eq!(map[&y], cons);
+        assert_eq!(map[&a], one);
+        assert_eq!(map[&b], zero);
+
+        let mut map: HashMap<Symbol, u8> = HashMap::new();
+        assert!(p.matches(x, &g, cons, &mut map, MatchMode::Identity, &mut NoTrace).is_err());
+    }
+}
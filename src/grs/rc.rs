@@ -0,0 +1,545 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides a reference-counted data graph.
+ *
+ * Each node records how many references point to it: one for every
+ * occurrence as an argument of another node, plus one each for being
+ * the root or being pinned. When a count drops to zero the node is
+ * freed immediately, and its own arguments are released in turn.
+ *
+ * Each node also records which nodes refer to it, so `redirect` only
+ * visits the referrers of the source, and the graph implements
+ * `Referrers`.
+ *
+ * Freshly allocated nodes start with a count of zero, and are not
+ * reclaimed until something has referred to them and then let go. As
+ * with any reference counting scheme, garbage cycles are never
+ * reclaimed eagerly. A fallback tracing collector, run via `gc`,
+ * picks up both cases.
+ *
+ * This exists mainly to benchmark against `arena::VecGraph`.
+ */
+use core::fmt::{self, Debug, Display};
+use crate::grs::{DataGraph, DataGraphBody, DenseId, Referrers, Types};
+use crate::grs::traverse::reachable_from;
+use crate::printer::Shorthand;
+
+
+struct Node<T: Types> {
+    value: T::Val,
+    args: Vec<T::Id>,
+    count: usize,
+    // One entry per argument position referring to this node.
+    referrers: Vec<T::Id>,
+}
+
+
+enum Slot<T: Types> {
+    Live(Node<T>),
+    Free,
+}
+
+
+/**
+ * Reference-counted implementation of `DataGraph`.
+ *
+ * The root is the first node allocated, unless set explicitly with
+ * `set_root`.
+ */
+pub struct RcGraph<T: Types> where T::Id: DenseId {
+    slots: Vec<Slot<T>>,
+    free: Vec<T::Id>,
+    pinned: Vec<T::Id>,
+    root: Option<T::Id>,
+    live: usize,
+    allocated: usize,
+    threshold: Option<usize>,
+}
+
+
+impl<T: Types> RcGraph<T> where T::Id: DenseId {
+    pub fn with_capacity(capacity: usize) -> Self {
+        RcGraph {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            pinned: Vec::new(),
+            root: None,
+            live: 0,
+            allocated: 0,
+            threshold: None,
+        }
+    }
+
+    /**
+     * The number of live nodes in the graph.
+     */
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /**
+     * Allocate a node, or return `None` if the id space of `T::Id`
+     * has been exhausted.
+     */
+    pub fn try_alloc(&mut self, value: T::Val) -> Option<T::Id> {
+        let node = Slot::Live(Node {
+            value,
+            args: Vec::new(),
+            count: 0,
+            referrers: Vec::new()
+        });
+        let id = if let Some(id) = self.free.pop() {
+            self.slots[id.index()] = node;
+            id
+        } else {
+            let id = T::Id::from_index(self.slots.len())?;
+            self.slots.push(node);
+            id
+        };
+
+        self.live += 1;
+        self.allocated += 1;
+        if self.root.is_none() {
+            self.set_root(id);
+        }
+        Some(id)
+    }
+
+    pub fn set_root(&mut self, id: T::Id) {
+        self.retain(id);
+        if let Some(old) = self.root.replace(id) {
+            self.release(old);
+        }
+    }
+
+    /**
+     * Hold an external reference to `id`.
+     */
+    pub fn pin(&mut self, id: T::Id) {
+        self.retain(id);
+        self.pinned.push(id);
+    }
+
+    /**
+     * Drop an external reference to `id`, which may free it.
+     */
+    pub fn unpin(&mut self, id: T::Id) {
+        if let Some(i) = self.pinned.iter().position(|p| *p == id) {
+            self.pinned.swap_remove(i);
+            self.release(id);
+        }
+    }
+
+    /**
+     * The number of references to `id`.
+     */
+    pub fn ref_count(&self, id: T::Id) -> usize {
+        self.node(id).count
+    }
+
+    /**
+     * Request a cycle collection once `threshold` nodes have been
+     * allocated since the last one. `None` disables automatic
+     * collection.
+     */
+    pub fn set_gc_threshold(&mut self, threshold: Option<usize>) {
+        self.threshold = threshold;
+    }
+
+    fn retain(&mut self, id: T::Id) {
+        self.node_mut(id).count += 1;
+    }
+
+    /**
+     * Drop one reference to `id`, freeing it and releasing its
+     * arguments if this was the last one.
+     */
+    fn release(&mut self, id: T::Id) {
+        // Each entry is a node to release, and the freed node whose
+        // argument it was, if any.
+        let mut pending = vec![(id, None)];
+        while let Some((id, referrer)) = pending.pop() {
+            let node = self.node_mut(id);
+            if let Some(referrer) = referrer {
+                forget(&mut node.referrers, referrer);
+            }
+            node.count -= 1;
+            if node.count == 0 {
+                if let Slot::Live(node) = core::mem::replace(
+                    &mut self.slots[id.index()],
+                    Slot::Free
+                ) {
+                    pending.extend(node.args.into_iter().map(|arg| (arg, Some(id))));
+                }
+                self.free.push(id);
+                self.live -= 1;
+            }
+        }
+    }
+
+    /**
+     * Free every node not reachable from the root or the pinned
+     * handles, returning the number of nodes reclaimed.
+     *
+     * This is the fallback for garbage cycles, and for nodes which
+     * were allocated but never referenced.
+     */
+    pub fn collect_cycles(&mut self) -> usize {
+        let marks = reachable_from(&*self, self.pinned.iter().copied().chain(self.root));
+
+        let mut reclaimed = 0;
+        let mut freed = Vec::new();
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            // Every slot index was a valid id when allocated.
+            let id = T::Id::from_index(i).unwrap();
+            if let Slot::Live(_) = slot {
+                if !marks.contains(id) {
+                    if let Slot::Live(node) = core::mem::replace(slot, Slot::Free) {
+                        freed.extend(node.args.into_iter().map(|arg| (arg, id)));
+                    }
+                    self.free.push(id);
+                    reclaimed += 1;
+                }
+            }
+        }
+
+        // Garbage may still refer to live nodes, whose counts and
+        // referrers must be adjusted. Reachable nodes always keep a
+        // count above zero.
+        for (arg, referrer) in freed {
+            if marks.contains(arg) {
+                let node = self.node_mut(arg);
+                forget(&mut node.referrers, referrer);
+                node.count -= 1;
+            }
+        }
+
+        self.live -= reclaimed;
+        self.allocated = 0;
+        reclaimed
+    }
+
+    fn node(&self, id: T::Id) -> &Node<T> {
+        match &self.slots[id.index()] {
+            Slot::Live(node) => node,
+            Slot::Free => panic!("dangling id {:?}", id),
+        }
+    }
+
+    fn node_mut(&mut self, id: T::Id) -> &mut Node<T> {
+        match &mut self.slots[id.index()] {
+            Slot::Live(node) => node,
+            Slot::Free => panic!("dangling id {:?}", id),
+        }
+    }
+}
+
+
+impl<T: Types> Default for RcGraph<T> where T::Id: DenseId {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+
+// Renders the graph in shorthand notation. See `printer`.
+impl<T: Types> Display for RcGraph<T> where T::Id: DenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            Shorthand::empty(self).fmt(f)
+        } else {
+            Shorthand::new(self).fmt(f)
+        }
+    }
+}
+
+
+impl<T: Types> Debug for RcGraph<T> where T::Id: DenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.slots.iter().enumerate().filter_map(
+                |(i, slot)| match slot {
+                    Slot::Live(node) => Some(
+                        (i, (node.value, &node.args, node.count))
+                    ),
+                    Slot::Free => None,
+                }
+            ))
+            .finish()
+    }
+}
+
+
+impl<'a, T: Types> DataGraphBody<'a, T> for RcGraph<T> where T::Id: DenseId {
+    type It = core::iter::Copied<core::slice::Iter<'a, T::Id>>;
+
+    fn new() -> Self { Self::default() }
+
+    fn args(&'a self, id: T::Id) -> Self::It {
+        self.node(id).args.iter().copied()
+    }
+
+    fn value(&'a self, id: T::Id) -> T::Val {
+        self.node(id).value
+    }
+
+    fn alloc(&'a mut self, func: T::Val) -> T::Id {
+        self.try_alloc(func).expect("storage exhausted")
+    }
+
+    fn append_arg(&'a mut self, id: T::Id, arg: T::Id) {
+        self.retain(arg);
+        self.node_mut(arg).referrers.push(id);
+        self.node_mut(id).args.push(arg);
+    }
+
+    /**
+     * Every reference to `src` is replaced with a reference to
+     * `dst`, including the root and any pins, as with the
+     * indirections of `VecGraph`. That leaves `src` unreferenced, so
+     * it is freed.
+     *
+     * Only the referrers of `src` and the pins are visited.
+     */
+    fn redirect(&'a mut self, src: T::Id, dst: T::Id) {
+        if src == dst {
+            return;
+        }
+
+        // Each entry accounts for exactly one argument position.
+        let referrers = core::mem::take(&mut self.node_mut(src).referrers);
+        let mut moved = referrers.len();
+        for referrer in referrers.iter().copied() {
+            let args = &mut self.node_mut(referrer).args;
+            if let Some(arg) = args.iter_mut().find(|arg| **arg == src) {
+                *arg = dst;
+            }
+        }
+        self.node_mut(dst).referrers.extend(referrers);
+
+        if self.root == Some(src) {
+            self.root = Some(dst);
+            moved += 1;
+        }
+
+        for pin in self.pinned.iter_mut().filter(|pin| **pin == src) {
+            *pin = dst;
+            moved += 1;
+        }
+
+        // Take the new references before dropping the old ones, in
+        // case `dst` is only reachable through `src`.
+        self.node_mut(dst).count += moved;
+        for _ in 0..moved {
+            self.release(src);
+        }
+    }
+
+    fn root(&'a self) -> T::Id {
+        self.root.expect("graph has no root")
+    }
+
+    fn gc(&'a mut self) {
+        self.collect_cycles();
+    }
+
+    fn wants_gc(&'a self) -> bool {
+        match self.threshold {
+            Some(threshold) => self.allocated >= threshold,
+            None => false,
+        }
+    }
+}
+
+
+impl<T: Types> DataGraph<T> for RcGraph<T> where T::Id: DenseId {}
+
+
+impl<T: Types> Referrers<T> for RcGraph<T> where T::Id: DenseId {
+    fn referrers(&self, id: T::Id) -> &[T::Id] {
+        &self.node(id).referrers
+    }
+}
+
+
+// Remove one occurrence of `id` from a referrer list.
+fn forget<Id: PartialEq>(referrers: &mut Vec<Id>, id: Id) {
+    if let Some(i) = referrers.iter().position(|r| *r == id) {
+        referrers.swap_remove(i);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::tests::{TestTypes, Value};
+    use Value::*;
+
+    #[test]
+    fn test_counts() {
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let add = g.alloc(Add);
+        let x = g.alloc(Int(1));
+        g.append_arg(add, x);
+        g.append_arg(add, x);
+
+        assert_eq!(g.ref_count(add), 1);
+        assert_eq!(g.ref_count(x), 2);
+        assert_eq!(g.args(add).collect::<Vec<_>>(), vec![x, x]);
+    }
+
+    #[test]
+    fn test_eager_free() {
+        // Start (Hd (Cons 1 Zero)) with Hd redirected to 1.
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let start = g.alloc(Start);
+        let hd = g.alloc(Hd);
+        let cons = g.alloc(Cons);
+        let one = g.alloc(Int(1));
+        let zero = g.alloc(Zero);
+        g.append_arg(start, hd);
+        g.append_arg(hd, cons);
+        g.append_arg(cons, one);
+        g.append_arg(cons, zero);
+        assert_eq!(g.len(), 5);
+
+        g.redirect(hd, one);
+        assert_eq!(g.len(), 2);
+        assert_eq!(g.args(start).collect::<Vec<_>>(), vec![one]);
+        assert_eq!(g.ref_count(one), 1);
+
+        // The freed slots are reused.
+        let n = g.alloc(Succ);
+        assert!(n == hd || n == cons || n == zero);
+    }
+
+    #[test]
+    fn test_redirect_root() {
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let succ = g.alloc(Succ);
+        let zero = g.alloc(Zero);
+        g.append_arg(succ, zero);
+
+        g.redirect(succ, zero);
+        assert_eq!(g.root(), zero);
+        assert_eq!(g.ref_count(zero), 1);
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let start = g.alloc(Start);
+        let a = g.alloc(Cons);
+        let b = g.alloc(Cons);
+        let unused = g.alloc(Zero);
+        let pinned = g.alloc(Zero);
+        g.append_arg(start, a);
+        g.append_arg(a, b);
+        g.append_arg(b, a);
+        g.pin(pinned);
+
+        // Moving the root frees `start`, but leaves a garbage cycle.
+        g.set_root(pinned);
+        assert_eq!(g.len(), 4);
+        assert_eq!(g.ref_count(a), 1);
+        assert_eq!(g.referrers(a), &[b]);
+        assert_eq!(g.collect_cycles(), 3);
+        assert_eq!(g.len(), 1);
+        assert_eq!(g.ref_count(pinned), 2);
+        assert!(g.free.contains(&unused));
+
+        g.unpin(pinned);
+        assert_eq!(g.ref_count(pinned), 1);
+        assert_eq!(g.len(), 1);
+    }
+
+    #[test]
+    fn test_referrers() {
+        // Start (Add x x) (Succ x), with Add redirected to Succ.
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let start = g.alloc(Start);
+        let add = g.alloc(Add);
+        let succ = g.alloc(Succ);
+        let x = g.alloc(Int(1));
+        g.append_arg(start, add);
+        g.append_arg(start, succ);
+        g.append_arg(add, x);
+        g.append_arg(add, x);
+        g.append_arg(succ, x);
+
+        assert_eq!(g.in_degree(x), 3);
+        assert_eq!(g.referrers(add), &[start]);
+
+        g.redirect(add, succ);
+        assert_eq!(g.args(start).collect::<Vec<_>>(), vec![succ, succ]);
+        assert_eq!(g.referrers(succ), &[start, start]);
+        assert_eq!(g.referrers(x), &[succ]);
+        assert_eq!(g.ref_count(x), 1);
+
+        // A self-referencing source moves its own reference too.
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let start = g.alloc(Start);
+        let loop_ = g.alloc(Cons);
+        let zero = g.alloc(Zero);
+        g.append_arg(start, loop_);
+        g.append_arg(loop_, loop_);
+        g.redirect(loop_, zero);
+        assert_eq!(g.args(start).collect::<Vec<_>>(), vec![zero]);
+        assert_eq!(g.in_degree(zero), 1);
+        assert_eq!(g.len(), 2);
+    }
+
+    #[test]
+    fn test_redirect_pins() {
+        let mut g: RcGraph<TestTypes> = RcGraph::new();
+        let start = g.alloc(Start);
+        let succ = g.alloc(Succ);
+        let zero = g.alloc(Zero);
+        g.append_arg(start, succ);
+        g.append_arg(succ, zero);
+        g.pin(succ);
+        g.pin(succ);
+
+        // Pins follow the redirection, so `succ` is freed.
+        g.redirect(succ, zero);
+        assert_eq!(g.len(), 2);
+        assert_eq!(g.ref_count(zero), 3);
+
+        g.unpin(zero);
+        g.unpin(zero);
+        assert_eq!(g.ref_count(zero), 1);
+        assert_eq!(g.len(), 2);
+    }
+}
@@ -0,0 +1,407 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides `Reducer`, which drives reduction one step at
+ * a time.
+ *
+ * A `Reducer` is an `Iterator` of `Step`s, so callers can pause
+ * between steps, inspect the data graph, and resume. When iteration
+ * ends, `outcome` reports why.
+ *
+ * Steps come from either a `Strategy`, which indicates only the
+ * redex, or a `RuleStrategy`, which also chooses the rule. Both are
+ * driven by the same loop, so fuel and garbage collection behave
+ * identically.
+ */
+
+use core::marker::PhantomData;
+use crate::grs::{
+    step_outcome, DataGraph, GRS, Mapping, MatchFailure, Pattern,
+    ReduceOutcome, RuleIndex, RuleStrategy, Strategy, Tracer, Types
+};
+use crate::grs::trace::NoTrace;
+
+
+/**
+ * A record of a single reduction step.
+ *
+ * The garbage collector only runs at the start of the following
+ * step, so the ids recorded here stay valid until the reducer is
+ * advanced again.
+ */
+#[derive(Debug, PartialEq)]
+pub struct Step<T: Types> {
+    // The root of the redex.
+    pub node: T::Id,
+    // The rule which was applied.
+    pub rule: RuleIndex,
+    // The nodes allocated by the contractum, in allocation order.
+    pub allocated: Vec<T::Id>,
+}
+
+
+/**
+ * Where a `Reducer` finds its next step: the redex, and the rule to
+ * reduce it by if the source chooses one. `None` for the rule means
+ * the first rule which matches, as in `GRS::reduce`.
+ */
+pub trait StepSource<T: Types> {
+    fn next_step(&mut self, dg: &impl DataGraph<T>) -> Option<(T::Id, Option<RuleIndex>)>;
+}
+
+
+/**
+ * Drives a `Reducer` from a `Strategy`. See `Reducer::new`.
+ */
+pub struct Redexes<S>(S);
+
+
+impl<T: Types, S: Strategy<T>> StepSource<T> for Redexes<S> {
+    fn next_step(&mut self, dg: &impl DataGraph<T>) -> Option<(T::Id, Option<RuleIndex>)> {
+        self.0.next_redex(dg).map(|node| (node, None))
+    }
+}
+
+
+/**
+ * Drives a `Reducer` from a `RuleStrategy`. See `Reducer::with_rules`.
+ */
+pub struct Rules<S>(S);
+
+
+impl<T: Types, S: RuleStrategy<T>> StepSource<T> for Rules<S> {
+    fn next_step(&mut self, dg: &impl DataGraph<T>) -> Option<(T::Id, Option<RuleIndex>)> {
+        self.0.next_step(dg).map(|(node, rule)| (node, Some(rule)))
+    }
+}
+
+
+/**
+ * Reduces a data graph one step at a time.
+ */
+pub struct Reducer<'g, T, D, P, S, M, R = NoTrace>
+where T: Types,
+      D: DataGraph<T>,
+      P: Pattern<T>,
+      S: StepSource<T>,
+      M: Mapping<T>,
+      R: Tracer<T>
+{
+    grs: &'g GRS<T, P>,
+    data: &'g mut D,
+    strategy: S,
+    tracer: R,
+    steps: usize,
+    outcome: Option<ReduceOutcome<T>>,
+    mapping: PhantomData<fn() -> M>,
+}
+
+impl<'g, T, D, P, S, M> Reducer<'g, T, D, P, S, M>
+where T: Types,
+      D: DataGraph<T>,
+      P: Pattern<T>,
+      S: StepSource<T>,
+      M: Mapping<T>
+{
+    fn from_source(grs: &'g GRS<T, P>, data: &'g mut D, strategy: S) -> Self {
+        Self {
+            grs,
+            data,
+            strategy,
+            tracer: NoTrace,
+            steps: 0,
+            outcome: None,
+            mapping: PhantomData,
+        }
+    }
+}
+
+impl<'g, T, D, P, S, M> Reducer<'g, T, D, P, Redexes<S>, M>
+where T: Types,
+      D: DataGraph<T>,
+      P: Pattern<T>,
+      S: Strategy<T>,
+      M: Mapping<T>
+{
+    /**
+     * Reduce at the redexes indicated by `strategy`, by the first
+     * rule which matches.
+     */
+    pub fn new(grs: &'g GRS<T, P>, data: &'g mut D, strategy: S) -> Self {
+        Self::from_source(grs, data, Redexes(strategy))
+    }
+}
+
+impl<'g, T, D, P, S, M> Reducer<'g, T, D, P, Rules<S>, M>
+where T: Types,
+      D: DataGraph<T>,
+      P: Pattern<T>,
+      S: RuleStrategy<T>,
+      M: Mapping<T>
+{
+    /**
+     * Reduce by the redexes and rules indicated by `strategy`.
+     */
+    pub fn with_rules(grs: &'g GRS<T, P>, data: &'g mut D, strategy: S) -> Self {
+        Self::from_source(grs, data, Rules(strategy))
+    }
+}
+
+impl<'g, T, D, P, S, M, R> Reducer<'g, T, D, P, S, M, R>
+where T: Types,
+      D: DataGraph<T>,
+      P: Pattern<T>,
+      S: StepSource<T>,
+      M: Mapping<T>,
+      R: Tracer<T>
+{
+    /**
+     * Replace the tracer which observes each step.
+     */
+    pub fn with_tracer<U: Tracer<T>>(self, tracer: U) -> Reducer<'g, T, D, P, S, M, U> {
+        Reducer {
+            grs: self.grs,
+            data: self.data,
+            strategy: self.strategy,
+            tracer,
+            steps: self.steps,
+            outcome: self.outcome,
+            mapping: PhantomData,
+        }
+    }
+
+    /**
+     * The data graph as it stands after the steps taken so far.
+     */
+    pub fn data(&self) -> &D {
+        self.data
+    }
+
+    /**
+     * The number of steps taken so far.
+     */
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /**
+     * Why iteration ended, or `None` if it has not.
+     */
+    pub fn outcome(&self) -> Option<&ReduceOutcome<T>> {
+        self.outcome.as_ref()
+    }
+
+    /**
+     * Take at most `fuel` further steps, and report how the run
+     * ended.
+     */
+    pub fn run(mut self, fuel: Option<usize>) -> ReduceOutcome<T> {
+        let limit = fuel.map(|fuel| self.steps + fuel);
+        while limit != Some(self.steps) {
+            if self.next().is_none() {
+                return self.outcome.expect("reducer stopped without an outcome");
+            }
+        }
+
+        match self.outcome {
+            Some(outcome) => outcome,
+            None => match self.strategy.next_step(self.data) {
+                Some(_) => ReduceOutcome::OutOfFuel {steps: self.steps},
+                None => ReduceOutcome::NormalForm {steps: self.steps},
+            }
+        }
+    }
+}
+
+impl<'g, T, D, P, S, M, R> Iterator for Reducer<'g, T, D, P, S, M, R>
+where T: Types,
+      D: DataGraph<T>,
+      P: Pattern<T>,
+      S: StepSource<T>,
+      M: Mapping<T>,
+      R: Tracer<T>
+{
+    type Item = Step<T>;
+
+    fn next(&mut self) -> Option<Step<T>> {
+        if self.outcome.is_some() {
+            return None;
+        }
+
+        // Collect garbage left by the previous step, now that the
+        // caller is done inspecting it.
+        if self.data.wants_gc() {
+            self.tracer.gc();
+            self.data.gc();
+        }
+
+        let (node, rule) = match self.strategy.next_step(self.data) {
+            Some(step) => step,
+            None => {
+                self.outcome = Some(ReduceOutcome::NormalForm {steps: self.steps});
+                return None;
+            }
+        };
+
+        let mut collect = Collect {inner: &mut self.tracer, allocated: Vec::new()};
+        let step = match rule {
+            Some(rule) => self.grs
+                .reduce_with::<D, M>(self.data, node, rule, &mut collect)
+                .map(|()| rule),
+            None => self.grs.reduce::<D, M>(self.data, node, &mut collect),
+        };
+        match step_outcome(step) {
+            Ok(rule) => {
+                let allocated = collect.allocated;
+                self.steps += 1;
+                Some(Step {node, rule, allocated})
+            },
+            Err(outcome) => {
+                self.outcome = Some(outcome);
+                None
+            }
+        }
+    }
+}
+
+
+// Records allocations on behalf of a step, forwarding every event to
+// the caller's tracer.
+struct Collect<'r, T: Types, R> {
+    inner: &'r mut R,
+    allocated: Vec<T::Id>,
+}
+
+impl<'r, T: Types, R: Tracer<T>> Tracer<T> for Collect<'r, T, R> {
+    fn attempt(&mut self, node: T::Id) {
+        self.inner.attempt(node)
+    }
+
+    fn bind(&mut self, var: T::Var, id: T::Id) {
+        self.inner.bind(var, id)
+    }
+
+    fn fail(&mut self, failure: &MatchFailure<T>) {
+        self.inner.fail(failure)
+    }
+
+    fn alloc(&mut self, id: T::Id) {
+        self.allocated.push(id);
+        self.inner.alloc(id)
+    }
+
+    fn redirect(&mut self, src: T::Id, dst: T::Id) {
+        self.inner.redirect(src, dst)
+    }
+
+    fn gc(&mut self) {
+        self.inner.gc()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::DataGraphBody;
+    use crate::grs::strategy::{Outermost, Priority};
+    use crate::grs::strategy::tests::{arith, graph, M};
+    use crate::grs::tests::Value::*;
+
+    #[test]
+    fn test_steps() {
+        let grs = arith();
+
+        // Start (Add Zero (Succ Zero))
+        let mut g = graph(&[(Start, &[1]), (Add, &[2, 3]), (Zero, &[]), (Succ, &[2])]);
+        let strategy = Outermost::<_, _, M>::new(&grs);
+        let mut reducer = Reducer::<_, _, _, _, M>::new(&grs, &mut g, strategy);
+
+        assert_eq!(reducer.next(), Some(Step {node: 1, rule: 1, allocated: vec![4, 5]}));
+        assert_eq!(reducer.data().value(4), Succ);
+        assert_eq!(reducer.steps(), 1);
+        assert_eq!(reducer.outcome(), None);
+
+        assert_eq!(reducer.next(), Some(Step {node: 5, rule: 0, allocated: vec![]}));
+        assert_eq!(reducer.next(), None);
+        assert_eq!(reducer.outcome(), Some(&ReduceOutcome::NormalForm {steps: 2}));
+        assert_eq!(reducer.next(), None);
+    }
+
+    #[test]
+    fn test_run() {
+        let grs = arith();
+
+        // Pause after one step, then resume with fresh fuel.
+        let mut g = graph(&[(Start, &[1]), (W, &[])]);
+        let strategy = Outermost::<_, _, M>::new(&grs);
+        let mut reducer = Reducer::<_, _, _, _, M>::new(&grs, &mut g, strategy);
+        assert!(reducer.next().is_some());
+        assert_eq!(reducer.run(Some(3)), ReduceOutcome::OutOfFuel {steps: 4});
+    }
+
+    #[test]
+    fn test_gc_between_steps() {
+        let grs = arith();
+
+        // The redex of a step survives until the next step, even
+        // though the graph asks for a collection after every one.
+        let mut g = graph(&[(Start, &[1]), (W, &[])]);
+        g.set_gc_threshold(Some(1));
+        let strategy = Outermost::<_, _, M>::new(&grs);
+        let mut reducer = Reducer::<_, _, _, _, M>::new(&grs, &mut g, strategy);
+
+        let step = reducer.next().unwrap();
+        assert_eq!(step, Step {node: 1, rule: 3, allocated: vec![2]});
+        assert_eq!(reducer.data().value(step.node), W);
+        assert_eq!(reducer.data().value(2), W);
+
+        // The collection before the next step frees the old redex,
+        // whose slot is then reused.
+        let step = reducer.next().unwrap();
+        assert_eq!(step, Step {node: 2, rule: 3, allocated: vec![1]});
+        assert_eq!(reducer.data().value(step.node), W);
+    }
+
+    #[test]
+    fn test_rules() {
+        let grs = arith();
+
+        // A rule strategy is stepped, and collected after, exactly
+        // like a plain one.
+        let mut g = graph(&[(Start, &[1]), (W, &[])]);
+        g.set_gc_threshold(Some(1));
+        let strategy = Priority::<_, _, M, _>::new(&grs, Outermost::<_, _, M>::new(&grs));
+        let mut reducer = Reducer::<_, _, _, _, M>::with_rules(&grs, &mut g, strategy);
+
+        assert_eq!(reducer.next(), Some(Step {node: 1, rule: 3, allocated: vec![2]}));
+        assert_eq!(reducer.data().value(1), W);
+        assert_eq!(reducer.next(), Some(Step {node: 2, rule: 3, allocated: vec![1]}));
+        assert_eq!(reducer.run(Some(2)), ReduceOutcome::OutOfFuel {steps: 4});
+    }
+}
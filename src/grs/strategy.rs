@@ -0,0 +1,720 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides implementations of `Strategy`.
+ *
+ * All of these walk the data graph from its root using the
+ * traversals in `traverse`, and so require dense ids. Each node is
+ * visited at most once per step, so shared and cyclic graphs are
+ * handled without special cases.
+ */
+use core::marker::PhantomData;
+use crate::grs::{DataGraph, DenseId, GRS, Mapping, Pattern, Strategy, Types};
+use crate::grs::{Mismatch, RuleIndex, RuleStrategy};
+use crate::grs::traverse::{Dfs, PostOrder};
+
+
+/**
+ * Leftmost-innermost, a.k.a. applicative order, strategy.
+ *
+ * Indicates the first node, in a left-to-right post-order walk from
+ * the root, at which any rule of the GRS matches. Such a node has no
+ * redex beneath it, except possibly through a cycle.
+ *
+ * `M` is the mapping used to test rules for a match.
+ */
+pub struct Innermost<'g, T, P, M>
+where T: Types, P: Pattern<T>, M: Mapping<T>
+{
+    grs: &'g GRS<T, P>,
+    mapping: PhantomData<fn() -> M>,
+}
+
+
+impl<'g, T, P, M> Innermost<'g, T, P, M>
+where T: Types, P: Pattern<T>, M: Mapping<T>
+{
+    pub fn new(grs: &'g GRS<T, P>) -> Self {
+        Innermost {grs, mapping: PhantomData}
+    }
+}
+
+
+impl<'g, T, P, M> Strategy<T> for Innermost<'g, T, P, M>
+where T: Types, T::Id: DenseId, P: Pattern<T>, M: Mapping<T>
+{
+    fn next_redex(&mut self, dg: &impl DataGraph<T>) -> Option<T::Id> {
+        PostOrder::new(dg, dg.root())
+            .find(|id| self.grs.matches::<M>(dg, *id).is_some())
+    }
+}
+
+
+/**
+ * Leftmost-outermost, a.k.a. normal order, strategy.
+ *
+ * Indicates the first node, in a left-to-right pre-order walk from
+ * the root, at which any rule of the GRS matches.
+ *
+ * Since no argument is reduced before the redex which might discard
+ * it, this strategy reaches a normal form whenever one exists, at
+ * least for left-normal systems. The price is that it can take more
+ * steps than `Innermost` when an argument would have been needed
+ * anyway.
+ *
+ * `M` is the mapping used to test rules for a match.
+ */
+pub struct Outermost<'g, T, P, M>
+where T: Types, P: Pattern<T>, M: Mapping<T>
+{
+    grs: &'g GRS<T, P>,
+    mapping: PhantomData<fn() -> M>,
+}
+
+
+impl<'g, T, P, M> Outermost<'g, T, P, M>
+where T: Types, P: Pattern<T>, M: Mapping<T>
+{
+    pub fn new(grs: &'g GRS<T, P>) -> Self {
+        Outermost {grs, mapping: PhantomData}
+    }
+}
+
+
+impl<'g, T, P, M> Strategy<T> for Outermost<'g, T, P, M>
+where T: Types, T::Id: DenseId, P: Pattern<T>, M: Mapping<T>
+{
+    fn next_redex(&mut self, dg: &impl DataGraph<T>) -> Option<T::Id> {
+        Dfs::new(dg, dg.root())
+            .find(|id| self.grs.matches::<M>(dg, *id).is_some())
+    }
+}
+
+
+// Evaluation state of a node within a single `Functional` step.
+#[derive(Copy, Clone, PartialEq)]
+enum Eval {
+    Unknown,
+    Active,
+    RootNormal,
+}
+
+
+/**
+ * The functional strategy of FPPGR, as used by Clean.
+ *
+ * To bring a node to root normal form, the rules are tried in
+ * textual order. Where a rule needs a particular symbol in an
+ * argument position, and the data has something else, the argument
+ * is first brought to root normal form in turn. Only if it is
+ * already in root normal form does the rule fail, and the next rule
+ * get tried. A node at which no rule applies is in root normal form.
+ *
+ * The strategy indicates the first redex this process arrives at.
+ * Once the root is in root normal form, its arguments are evaluated
+ * in the same way, left to right, so that repeated steps reach a
+ * normal form.
+ *
+ * A node which demands its own value through a cycle is treated as
+ * being in root normal form, rather than looping.
+ *
+ * `M` is the mapping used to test rules for a match.
+ */
+pub struct Functional<'g, T, P, M>
+where T: Types, P: Pattern<T>, M: Mapping<T>
+{
+    grs: &'g GRS<T, P>,
+    mapping: PhantomData<fn() -> M>,
+}
+
+
+impl<'g, T, P, M> Functional<'g, T, P, M>
+where T: Types, T::Id: DenseId, P: Pattern<T>, M: Mapping<T>
+{
+    pub fn new(grs: &'g GRS<T, P>) -> Self {
+        Functional {grs, mapping: PhantomData}
+    }
+
+    /**
+     * Return the redex which must be reduced next in order to bring
+     * `node` to root normal form, or `None` if it already is.
+     *
+     * Demanded arguments are tracked on an explicit stack, so long
+     * chains of demand can't overflow the call stack.
+     */
+    fn demand(
+        &self,
+        dg: &impl DataGraph<T>,
+        node: T::Id,
+        state: &mut Vec<Eval>
+    ) -> Option<T::Id> {
+        if !activate(state, node) {
+            return None;
+        }
+
+        // Each frame is a node under evaluation, and the index of the
+        // next rule to try there.
+        let mut frames = vec![(node, 0)];
+        while let Some(&(node, next)) = frames.last() {
+            let rule = match self.grs.rules().get(next) {
+                Some(rule) => rule,
+                None => {
+                    // No rule applies, so resume the demanding node.
+                    state[node.index()] = Eval::RootNormal;
+                    frames.pop();
+                    continue;
+                }
+            };
+            if let Some(frame) = frames.last_mut() {
+                frame.1 += 1;
+            }
+
+            let failure = match rule.matches::<M>(dg, node) {
+                Ok(_) => return Some(node),
+                Err(failure) => failure,
+            };
+
+            // Only a wrong symbol or arity can be fixed by reduction.
+            let needed = match failure.mismatch {
+                Mismatch::Symbol {..} | Mismatch::Arity {..} => failure.node != node,
+                Mismatch::Bound(_) => false,
+            };
+            if needed && activate(state, failure.node) {
+                frames.push((failure.node, 0));
+            }
+        }
+
+        None
+    }
+}
+
+
+// Mark `id` as under evaluation, returning `false` if it already was,
+// or has already been found to be in root normal form.
+fn activate<I: DenseId>(state: &mut Vec<Eval>, id: I) -> bool {
+    let i = id.index();
+    if i >= state.len() {
+        state.resize(i + 1, Eval::Unknown);
+    }
+    if state[i] != Eval::Unknown {
+        return false;
+    }
+    state[i] = Eval::Active;
+    true
+}
+
+
+impl<'g, T, P, M> Strategy<T> for Functional<'g, T, P, M>
+where T: Types, T::Id: DenseId, P: Pattern<T>, M: Mapping<T>
+{
+    fn next_redex(&mut self, dg: &impl DataGraph<T>) -> Option<T::Id> {
+        let mut state = Vec::new();
+        Dfs::new(dg, dg.root())
+            .find_map(|id| self.demand(dg, id, &mut state))
+    }
+}
+
+
+/**
+ * Adapts a `Strategy` into a `RuleStrategy`, by choosing the first
+ * rule which matches the indicated redex.
+ *
+ * This is exactly the rule priority used by `GRS::reduce`. If no rule
+ * matches the indicated node, the step names the rule one past the
+ * end of the GRS, so that the run ends in `ReduceError::NoSuchRule`
+ * rather than looking like a normal form.
+ */
+pub struct Priority<'g, T, P, M, S>
+where T: Types, P: Pattern<T>, M: Mapping<T>, S: Strategy<T>
+{
+    grs: &'g GRS<T, P>,
+    strategy: S,
+    mapping: PhantomData<fn() -> M>,
+}
+
+
+impl<'g, T, P, M, S> Priority<'g, T, P, M, S>
+where T: Types, P: Pattern<T>, M: Mapping<T>, S: Strategy<T>
+{
+    pub fn new(grs: &'g GRS<T, P>, strategy: S) -> Self {
+        Priority {grs, strategy, mapping: PhantomData}
+    }
+}
+
+
+impl<'g, T, P, M, S> RuleStrategy<T> for Priority<'g, T, P, M, S>
+where T: Types, P: Pattern<T>, M: Mapping<T>, S: Strategy<T>
+{
+    fn next_step(&mut self, dg: &impl DataGraph<T>) -> Option<(T::Id, RuleIndex)> {
+        let node = self.strategy.next_redex(dg)?;
+        let rule = self.grs.matches::<M>(dg, node).unwrap_or(self.grs.len());
+        Some((node, rule))
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::ast::canonical::Node;
+    use crate::grs::{reduce, reduce_rules, DataGraphBody, ReduceError, ReduceOutcome};
+    use crate::grs::arena::VecGraph;
+    use crate::grs::trace::NoTrace;
+    use crate::grs::tests::{rule, Symbol, TestGRS, TestTypes, Value, WideTypes};
+    use Symbol::*;
+    use Value::*;
+
+    pub(crate) type M = HashMap<Symbol, u8>;
+
+    // Peano addition, a rule which discards its argument, and one
+    // which never terminates.
+    //
+    //   Add x Zero     -> x
+    //   Add x (Succ y) -> Succ (Add x y)
+    //   Hd (Cons a b)  -> a
+    //   W              -> W
+    pub(crate) fn arith() -> TestGRS {
+        GRS::new()
+            .with_rule(rule(
+                vec![Node(m, Add, vec![x, n]), Node(n, Zero, vec![])],
+                vec![],
+                (m, x)
+            ).unwrap())
+            .with_rule(rule(
+                vec![Node(m, Add, vec![x, n]), Node(n, Succ, vec![y])],
+                vec![Node(o, Succ, vec![z]), Node(z, Add, vec![x, y])],
+                (m, o)
+            ).unwrap())
+            .with_rule(rule(
+                vec![Node(m, Hd, vec![n]), Node(n, Cons, vec![a, b])],
+                vec![],
+                (m, a)
+            ).unwrap())
+            .with_rule(rule(
+                vec![Node(m, W, vec![])],
+                vec![Node(n, W, vec![])],
+                (m, n)
+            ).unwrap())
+    }
+
+    // Run at most `limit` steps, returning the number taken if a
+    // normal form was reached.
+    pub(crate) fn steps(
+        grs: &TestGRS,
+        g: &mut VecGraph<TestTypes>,
+        mut strategy: impl Strategy<TestTypes>,
+        limit: usize
+    ) -> Option<usize> {
+        for step in 0..limit {
+            match strategy.next_redex(g) {
+                Some(node) => {
+                    grs.reduce::<_, M>(g, node, &mut NoTrace).unwrap();
+                },
+                None => return Some(step),
+            }
+        }
+        None
+    }
+
+    // Build a graph from (value, args) pairs, where args index into
+    // the list. The first node is the root.
+    pub(crate) fn graph(nodes: &[(Value, &[u8])]) -> VecGraph<TestTypes> {
+        let mut g = VecGraph::new();
+        for (value, _) in nodes {
+            g.alloc(*value);
+        }
+        for (id, (_, args)) in nodes.iter().enumerate() {
+            for arg in args.iter() {
+                g.append_arg(id as u8, *arg);
+            }
+        }
+        g
+    }
+
+    #[test]
+    fn test_innermost() {
+        let grs = arith();
+
+        // Start (Hd (Cons (Add Zero Zero) Zero))
+        let g = graph(&[
+            (Start, &[1]),
+            (Hd, &[2]),
+            (Cons, &[3, 4]),
+            (Add, &[4, 4]),
+            (Zero, &[]),
+        ]);
+        assert_eq!(Innermost::<_, _, M>::new(&grs).next_redex(&g), Some(3));
+
+        // Start (Add (Hd (Cons Zero Zero)) (Add Zero Zero))
+        let g = graph(&[
+            (Start, &[1]),
+            (Add, &[2, 5]),
+            (Hd, &[3]),
+            (Cons, &[4, 4]),
+            (Zero, &[]),
+            (Add, &[4, 4]),
+        ]);
+        assert_eq!(Innermost::<_, _, M>::new(&grs).next_redex(&g), Some(2));
+    }
+
+    #[test]
+    fn test_innermost_cycle() {
+        let grs = arith();
+
+        // x: Cons (Add Zero Zero) x
+        let g = graph(&[
+            (Cons, &[1, 0]),
+            (Add, &[2, 2]),
+            (Zero, &[]),
+        ]);
+        let mut strategy = Innermost::<_, _, M>::new(&grs);
+        assert_eq!(strategy.next_redex(&g), Some(1));
+
+        // x: Cons Zero x
+        let g = graph(&[(Cons, &[1, 0]), (Zero, &[])]);
+        assert_eq!(strategy.next_redex(&g), None);
+    }
+
+    #[test]
+    fn test_innermost_reduce() {
+        let grs = arith();
+
+        // Start (Add (Succ (Succ Zero)) (Succ Zero))
+        let mut g = graph(&[
+            (Start, &[1]),
+            (Add, &[2, 4]),
+            (Succ, &[3]),
+            (Succ, &[5]),
+            (Succ, &[5]),
+            (Zero, &[]),
+        ]);
+        let strategy = Innermost::<_, _, M>::new(&grs);
+        assert_eq!(
+            reduce::<_, _, _, _, M>(&grs, &mut g, strategy, None, &mut NoTrace),
+            ReduceOutcome::NormalForm {steps: 2}
+        );
+
+        let mut node = g.args(g.root()).next().unwrap();
+        for _ in 0..3 {
+            assert_eq!(g.value(node), Succ);
+            node = g.args(node).next().unwrap();
+        }
+        assert_eq!(g.value(node), Zero);
+    }
+
+    #[test]
+    fn test_outermost() {
+        let grs = arith();
+
+        // Start (Hd (Cons (Add Zero Zero) Zero))
+        let g = graph(&[
+            (Start, &[1]),
+            (Hd, &[2]),
+            (Cons, &[3, 4]),
+            (Add, &[4, 4]),
+            (Zero, &[]),
+        ]);
+        assert_eq!(Outermost::<_, _, M>::new(&grs).next_redex(&g), Some(1));
+
+        // Start (Cons (Succ Zero) (Add Zero Zero)), with Zero shared
+        let g = graph(&[
+            (Start, &[1]),
+            (Cons, &[2, 4]),
+            (Succ, &[3]),
+            (Zero, &[]),
+            (Add, &[3, 3]),
+        ]);
+        assert_eq!(Outermost::<_, _, M>::new(&grs).next_redex(&g), Some(4));
+    }
+
+    #[test]
+    fn test_outermost_cycle() {
+        let grs = arith();
+        let mut strategy = Outermost::<_, _, M>::new(&grs);
+
+        // x: Cons Zero (Cons (Add Zero Zero) x)
+        let g = graph(&[
+            (Cons, &[1, 2]),
+            (Zero, &[]),
+            (Cons, &[3, 0]),
+            (Add, &[1, 1]),
+        ]);
+        assert_eq!(strategy.next_redex(&g), Some(3));
+
+        // x: Cons Zero x
+        let g = graph(&[(Cons, &[1, 0]), (Zero, &[])]);
+        assert_eq!(strategy.next_redex(&g), None);
+    }
+
+    #[test]
+    fn test_step_counts() {
+        let grs = arith();
+
+        // Start (Hd (Cons Zero (Add (Succ Zero) (Succ Zero))))
+        let discard = || graph(&[
+            (Start, &[1]),
+            (Hd, &[2]),
+            (Cons, &[3, 4]),
+            (Zero, &[]),
+            (Add, &[5, 5]),
+            (Succ, &[3]),
+        ]);
+
+        let mut g = discard();
+        let outer = steps(&grs, &mut g, Outermost::<_, _, M>::new(&grs), 10);
+        assert_eq!(outer, Some(1));
+
+        let mut g = discard();
+        let inner = steps(&grs, &mut g, Innermost::<_, _, M>::new(&grs), 10);
+        assert_eq!(inner, Some(3));
+
+        // Start (Hd (Cons Zero W)) only has a normal form under
+        // normal order.
+        let diverge = || graph(&[
+            (Start, &[1]),
+            (Hd, &[2]),
+            (Cons, &[3, 4]),
+            (Zero, &[]),
+            (W, &[]),
+        ]);
+
+        let mut g = diverge();
+        let outer = steps(&grs, &mut g, Outermost::<_, _, M>::new(&grs), 10);
+        assert_eq!(outer, Some(1));
+        assert_eq!(g.value(g.args(0).next().unwrap()), Zero);
+
+        let mut g = diverge();
+        let inner = steps(&grs, &mut g, Innermost::<_, _, M>::new(&grs), 10);
+        assert_eq!(inner, None);
+    }
+
+    #[test]
+    fn test_functional() {
+        let grs = arith();
+
+        // Start (Add Zero (Hd (Cons (Succ Zero) (Add Zero Zero))))
+        //
+        // `Add x Zero` demands the second argument, which exposes the
+        // `Hd` redex. The inner `Add` is never needed.
+        let mut g = graph(&[
+            (Start, &[1]),
+            (Add, &[3, 2]),
+            (Hd, &[4]),
+            (Zero, &[]),
+            (Cons, &[5, 6]),
+            (Succ, &[3]),
+            (Add, &[3, 3]),
+        ]);
+        let mut strategy = Functional::<_, _, M>::new(&grs);
+        assert_eq!(strategy.next_redex(&g), Some(2));
+
+        grs.reduce::<_, M>(&mut g, 2, &mut NoTrace).unwrap();
+        assert_eq!(strategy.next_redex(&g), Some(1));
+
+        let result = steps(&grs, &mut g, strategy, 10);
+        assert_eq!(result, Some(2));
+    }
+
+    #[test]
+    fn test_functional_root_normal() {
+        let grs = arith();
+        let mut strategy = Functional::<_, _, M>::new(&grs);
+
+        // Add Zero (Cons W W) is stuck, since Cons is a constructor.
+        // Evaluation moves on to the arguments.
+        let g = graph(&[
+            (Add, &[1, 2]),
+            (Zero, &[]),
+            (Cons, &[3, 3]),
+            (W, &[]),
+        ]);
+        assert_eq!(strategy.next_redex(&g), Some(3));
+
+        // Start (Hd (Cons Zero W)) is lazy in the tail.
+        let mut g = graph(&[
+            (Start, &[1]),
+            (Hd, &[2]),
+            (Cons, &[3, 4]),
+            (Zero, &[]),
+            (W, &[]),
+        ]);
+        assert_eq!(steps(&grs, &mut g, strategy, 10), Some(1));
+    }
+
+    #[test]
+    fn test_functional_cycle() {
+        let grs = arith();
+        let mut strategy = Functional::<_, _, M>::new(&grs);
+
+        // x: Add Zero x demands itself.
+        let g = graph(&[(Add, &[1, 0]), (Zero, &[])]);
+        assert_eq!(strategy.next_redex(&g), None);
+
+        // x: Cons (Add Zero (Succ Zero)) x
+        let g = graph(&[
+            (Cons, &[1, 0]),
+            (Add, &[2, 3]),
+            (Zero, &[]),
+            (Succ, &[2]),
+        ]);
+        assert_eq!(strategy.next_redex(&g), Some(1));
+    }
+
+    #[test]
+    fn test_functional_deep() {
+        use core::convert::TryFrom;
+        use crate::ast::canonical::{self, Graph};
+        use crate::grs::Rule;
+        use crate::grs::pattern::VecPattern;
+
+        let grs: GRS<WideTypes, VecPattern<WideTypes>> = GRS::new()
+            .with_rule(Rule::try_from(canonical::Rule {
+                redex: Graph(vec![Node(m, Hd, vec![n]), Node(n, Cons, vec![a, b])]),
+                contractum: Graph(vec![]),
+                redirection: (m, a)
+            }).unwrap());
+
+        // Start (Hd (Hd ... (Hd (Cons Zero Zero)))), where only the
+        // innermost Hd is a redex.
+        const DEPTH: u32 = 200_000;
+        let mut g: VecGraph<WideTypes> = VecGraph::new();
+        let mut node = g.alloc(Start);
+        for _ in 0..DEPTH {
+            let hd = g.alloc(Hd);
+            g.append_arg(node, hd);
+            node = hd;
+        }
+        let cons = g.alloc(Cons);
+        let zero = g.alloc(Zero);
+        g.append_arg(node, cons);
+        g.append_arg(cons, zero);
+        g.append_arg(cons, zero);
+
+        let mut strategy = Functional::<_, _, HashMap<Symbol, u32>>::new(&grs);
+        assert_eq!(strategy.next_redex(&g), Some(DEPTH));
+    }
+
+    // Always points at the same node, redex or not.
+    struct At(u8);
+
+    impl Strategy<TestTypes> for At {
+        fn next_redex(&mut self, _: &impl DataGraph<TestTypes>) -> Option<u8> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_fuel() {
+        let grs = arith();
+
+        // W -> W never terminates.
+        let mut g = graph(&[(Start, &[1]), (W, &[])]);
+        let strategy = Outermost::<_, _, M>::new(&grs);
+        assert_eq!(
+            reduce::<_, _, _, _, M>(&grs, &mut g, strategy, Some(10), &mut NoTrace),
+            ReduceOutcome::OutOfFuel {steps: 10}
+        );
+
+        // Fuel is only consumed by steps actually taken.
+        let mut g = graph(&[(Start, &[1]), (Add, &[2, 2]), (Zero, &[])]);
+        let strategy = Outermost::<_, _, M>::new(&grs);
+        assert_eq!(
+            reduce::<_, _, _, _, M>(&grs, &mut g, strategy, Some(1), &mut NoTrace),
+            ReduceOutcome::NormalForm {steps: 1}
+        );
+
+        let mut g = graph(&[(Start, &[1]), (Zero, &[])]);
+        assert_eq!(
+            reduce::<_, _, _, _, M>(&grs, &mut g, At(1), None, &mut NoTrace),
+            ReduceOutcome::Stuck {node: 1}
+        );
+
+        // Adapted by `Priority`, the same non-redex is an error rather
+        // than a normal form.
+        let mut g = graph(&[(Start, &[1]), (Zero, &[])]);
+        let strategy = Priority::<_, _, M, _>::new(&grs, At(1));
+        assert_eq!(
+            reduce_rules::<_, _, _, _, M>(&grs, &mut g, strategy, None, &mut NoTrace),
+            ReduceOutcome::Error(ReduceError::NoSuchRule(grs.len()))
+        );
+    }
+
+    // Picks the last rule which matches at the outermost redex.
+    struct LastRule<'g>(&'g TestGRS);
+
+    impl<'g> RuleStrategy<TestTypes> for LastRule<'g> {
+        fn next_step(&mut self, dg: &impl DataGraph<TestTypes>) -> Option<(u8, RuleIndex)> {
+            let node = Outermost::<_, _, M>::new(self.0).next_redex(dg)?;
+            let rule = self.0.matching::<M, _>(dg, node).last()?;
+            Some((node, rule))
+        }
+    }
+
+    #[test]
+    fn test_rule_strategy() {
+        use crate::ast::canonical::Node;
+
+        // Two overlapping rules for Add, which disagree.
+        //
+        //   Add x y -> x
+        //   Add x y -> y
+        let grs: TestGRS = GRS::new()
+            .with_rule(rule(vec![Node(m, Add, vec![x, y])], vec![], (m, x)).unwrap())
+            .with_rule(rule(vec![Node(m, Add, vec![x, y])], vec![], (m, y)).unwrap());
+
+        // Start (Add Zero (Succ Zero))
+        let start = || graph(&[
+            (Start, &[1]),
+            (Add, &[2, 3]),
+            (Zero, &[]),
+            (Succ, &[2]),
+        ]);
+
+        assert_eq!(grs.matching::<M, _>(&start(), 1).collect::<Vec<_>>(), vec![0, 1]);
+
+        let mut g = start();
+        let priority = Priority::<_, _, M, _>::new(&grs, Outermost::<_, _, M>::new(&grs));
+        assert_eq!(
+            reduce_rules::<_, _, _, _, M>(&grs, &mut g, priority, None, &mut NoTrace),
+            ReduceOutcome::NormalForm {steps: 1}
+        );
+        assert_eq!(g.args(0).collect::<Vec<_>>(), vec![2]);
+
+        let mut g = start();
+        assert_eq!(
+            reduce_rules::<_, _, _, _, M>(&grs, &mut g, LastRule(&grs), None, &mut NoTrace),
+            ReduceOutcome::NormalForm {steps: 1}
+        );
+        assert_eq!(g.args(0).collect::<Vec<_>>(), vec![3]);
+
+        let mut g = start();
+        assert_eq!(grs.reduce_with::<_, M>(&mut g, 1, 2, &mut NoTrace), Err(ReduceError::NoSuchRule(2)));
+        assert_eq!(grs.reduce_with::<_, M>(&mut g, 2, 1, &mut NoTrace), Err(ReduceError::NoMatch(2)));
+    }
+}
@@ -0,0 +1,181 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides implementations of `Tracer`.
+ *
+ * `NoTrace` ignores every event, and is what callers should pass
+ * when they are not interested in tracing. `StdoutTracer` prints
+ * each event as it happens, which is useful when debugging a rule
+ * set.
+ */
+
+use crate::grs::{MatchFailure, Tracer, Types};
+use std::fmt::Debug;
+
+
+// Lets a caller lend out a tracer without giving it up.
+impl<T: Types, R: Tracer<T>> Tracer<T> for &mut R {
+    fn attempt(&mut self, node: T::Id) {
+        (**self).attempt(node)
+    }
+
+    fn bind(&mut self, var: T::Var, id: T::Id) {
+        (**self).bind(var, id)
+    }
+
+    fn fail(&mut self, failure: &MatchFailure<T>) {
+        (**self).fail(failure)
+    }
+
+    fn alloc(&mut self, id: T::Id) {
+        (**self).alloc(id)
+    }
+
+    fn redirect(&mut self, src: T::Id, dst: T::Id) {
+        (**self).redirect(src, dst)
+    }
+
+    fn gc(&mut self) {
+        (**self).gc()
+    }
+}
+
+
+/**
+ * A tracer which ignores every event.
+ */
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoTrace;
+
+impl<T: Types> Tracer<T> for NoTrace {}
+
+
+/**
+ * A tracer which prints every event to stdout.
+ */
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StdoutTracer;
+
+impl<T: Types + Debug> Tracer<T> for StdoutTracer {
+    fn attempt(&mut self, node: T::Id) {
+        println!("attempt: {:?}", node);
+    }
+
+    fn bind(&mut self, var: T::Var, id: T::Id) {
+        println!("bind: {:?} -> {:?}", var, id);
+    }
+
+    fn fail(&mut self, failure: &MatchFailure<T>) {
+        println!("fail: {:?}", failure);
+    }
+
+    fn alloc(&mut self, id: T::Id) {
+        println!("alloc: {:?}", id);
+    }
+
+    fn redirect(&mut self, src: T::Id, dst: T::Id) {
+        println!("redirect: {:?} -> {:?}", src, dst);
+    }
+
+    fn gc(&mut self) {
+        println!("gc");
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::Types;
+    use crate::grs::strategy::tests::{arith, graph, M};
+    use crate::grs::tests::{Symbol, TestTypes, Value};
+
+    #[derive(Debug, PartialEq)]
+    enum Event<T: Types> {
+        Attempt(T::Id),
+        Bind(T::Var, T::Id),
+        Fail(T::Var, T::Id),
+        Alloc(T::Id),
+        Redirect(T::Id, T::Id),
+    }
+
+    #[derive(Default)]
+    struct Record(Vec<Event<TestTypes>>);
+
+    impl Tracer<TestTypes> for Record {
+        fn attempt(&mut self, node: u8) {
+            self.0.push(Event::Attempt(node));
+        }
+
+        fn bind(&mut self, var: Symbol, id: u8) {
+            self.0.push(Event::Bind(var, id));
+        }
+
+        fn fail(&mut self, failure: &MatchFailure<TestTypes>) {
+            self.0.push(Event::Fail(failure.var, failure.node));
+        }
+
+        fn alloc(&mut self, id: u8) {
+            self.0.push(Event::Alloc(id));
+        }
+
+        fn redirect(&mut self, src: u8, dst: u8) {
+            self.0.push(Event::Redirect(src, dst));
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        use Event::*;
+        use Symbol::*;
+        use Value::*;
+
+        // Add 0 (Succ 0)
+        let grs = arith();
+        let mut g = graph(&[(Add, &[1, 2]), (Zero, &[]), (Succ, &[1])]);
+        let mut record = Record::default();
+        grs.reduce::<_, M>(&mut g, 0, &mut record).unwrap();
+
+        assert_eq!(record.0, vec![
+            // Add x Zero
+            Attempt(0),
+            Bind(m, 0),
+            Bind(x, 1),
+            Fail(n, 2),
+            // Add x (Succ y)
+            Attempt(0),
+            Bind(m, 0),
+            Bind(x, 1),
+            Bind(n, 2),
+            Bind(y, 1),
+            Alloc(3),
+            Alloc(4),
+            Redirect(0, 3),
+        ]);
+    }
+}
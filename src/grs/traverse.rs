@@ -0,0 +1,440 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module provides traversals over any `DataGraph`.
+ *
+ * Everything here is iterative rather than recursive, so deep graphs
+ * can't overflow the stack, and every node is visited at most once,
+ * so shared and cyclic graphs are handled without special cases.
+ * Arguments are always visited left to right.
+ *
+ * Visited sets are indexed by id, and so these require dense ids.
+ */
+use std::collections::VecDeque;
+use crate::grs::{DataGraph, DenseId, Types};
+
+
+/**
+ * A set of node ids, backed by a `bool` per id.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IdSet<I: DenseId> {
+    bits: Vec<bool>,
+    len: usize,
+    ids: core::marker::PhantomData<I>,
+}
+
+
+impl<I: DenseId> IdSet<I> {
+    pub fn new() -> Self {
+        IdSet {bits: Vec::new(), len: 0, ids: core::marker::PhantomData}
+    }
+
+    /**
+     * Add `id` to the set, returning `false` if it was already
+     * present.
+     */
+    pub fn insert(&mut self, id: I) -> bool {
+        let i = id.index();
+        if i >= self.bits.len() {
+            self.bits.resize(i + 1, false);
+        }
+        let new = !core::mem::replace(&mut self.bits[i], true);
+        if new {
+            self.len += 1;
+        }
+        new
+    }
+
+    /**
+     * Remove `id` from the set, returning `false` if it was not
+     * present.
+     */
+    pub fn remove(&mut self, id: I) -> bool {
+        let present = match self.bits.get_mut(id.index()) {
+            Some(bit) => core::mem::replace(bit, false),
+            None => false,
+        };
+        if present {
+            self.len -= 1;
+        }
+        present
+    }
+
+    pub fn contains(&self, id: I) -> bool {
+        self.bits.get(id.index()).copied().unwrap_or(false)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * The members of the set, in order of id.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = I> + '_ {
+        self.bits.iter()
+            .enumerate()
+            .filter(|(_, present)| **present)
+            // Only valid ids are ever inserted.
+            .map(|(i, _)| I::from_index(i).unwrap())
+    }
+}
+
+
+/**
+ * Depth-first, pre-order traversal from a starting node.
+ */
+pub struct Dfs<'g, T: Types, D> where T::Id: DenseId {
+    graph: &'g D,
+    seen: IdSet<T::Id>,
+    stack: Vec<T::Id>,
+    args: Vec<T::Id>,
+}
+
+
+impl<'g, T: Types, D: DataGraph<T>> Dfs<'g, T, D> where T::Id: DenseId {
+    pub fn new(graph: &'g D, start: T::Id) -> Self {
+        Self::from_roots(graph, Some(start))
+    }
+
+    /**
+     * Traverse from each of `roots` in turn, visiting every node
+     * once in total.
+     */
+    pub fn from_roots(graph: &'g D, roots: impl IntoIterator<Item = T::Id>) -> Self {
+        let mut stack: Vec<T::Id> = roots.into_iter().collect();
+        stack.reverse();
+        Dfs {graph, seen: IdSet::new(), stack, args: Vec::new()}
+    }
+}
+
+
+impl<'g, T: Types, D: DataGraph<T>> Iterator for Dfs<'g, T, D> where T::Id: DenseId {
+    type Item = T::Id;
+
+    fn next(&mut self) -> Option<T::Id> {
+        while let Some(id) = self.stack.pop() {
+            if self.seen.insert(id) {
+                self.args.extend(self.graph.args(id));
+                self.stack.extend(self.args.drain(..).rev());
+                return Some(id);
+            }
+        }
+        None
+    }
+}
+
+
+/**
+ * Breadth-first traversal from a starting node.
+ */
+pub struct Bfs<'g, T: Types, D> where T::Id: DenseId {
+    graph: &'g D,
+    seen: IdSet<T::Id>,
+    queue: VecDeque<T::Id>,
+}
+
+
+impl<'g, T: Types, D: DataGraph<T>> Bfs<'g, T, D> where T::Id: DenseId {
+    pub fn new(graph: &'g D, start: T::Id) -> Self {
+        let mut seen = IdSet::new();
+        seen.insert(start);
+        Bfs {graph, seen, queue: VecDeque::from(vec![start])}
+    }
+}
+
+
+impl<'g, T: Types, D: DataGraph<T>> Iterator for Bfs<'g, T, D> where T::Id: DenseId {
+    type Item = T::Id;
+
+    fn next(&mut self) -> Option<T::Id> {
+        let id = self.queue.pop_front()?;
+        for arg in self.graph.args(id) {
+            if self.seen.insert(arg) {
+                self.queue.push_back(arg);
+            }
+        }
+        Some(id)
+    }
+}
+
+
+/**
+ * Depth-first, post-order traversal from a starting node.
+ *
+ * Each node is yielded after all of its arguments, except those it
+ * can only reach through a cycle back to itself.
+ */
+pub struct PostOrder<'g, T: Types, D> where T::Id: DenseId {
+    graph: &'g D,
+    seen: IdSet<T::Id>,
+    // The flag is set once the node's arguments have been pushed.
+    stack: Vec<(T::Id, bool)>,
+    args: Vec<T::Id>,
+}
+
+
+impl<'g, T: Types, D: DataGraph<T>> PostOrder<'g, T, D> where T::Id: DenseId {
+    pub fn new(graph: &'g D, start: T::Id) -> Self {
+        PostOrder {graph, seen: IdSet::new(), stack: vec![(start, false)], args: Vec::new()}
+    }
+}
+
+
+impl<'g, T: Types, D: DataGraph<T>> Iterator for PostOrder<'g, T, D> where T::Id: DenseId {
+    type Item = T::Id;
+
+    fn next(&mut self) -> Option<T::Id> {
+        while let Some((id, expanded)) = self.stack.pop() {
+            if expanded {
+                return Some(id);
+            } else if self.seen.insert(id) {
+                self.stack.push((id, true));
+                self.args.extend(self.graph.args(id));
+                self.stack.extend(self.args.drain(..).rev().map(|arg| (arg, false)));
+            }
+        }
+        None
+    }
+}
+
+
+/**
+ * The set of nodes reachable from `start`, including `start` itself.
+ */
+pub fn reachable<T, D>(graph: &D, start: T::Id) -> IdSet<T::Id>
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    reachable_from(graph, Some(start))
+}
+
+
+/**
+ * The set of nodes reachable from any of `roots`, including the
+ * roots themselves. Collectors use this to mark from the root and
+ * the pinned handles together.
+ */
+pub fn reachable_from<T, D>(graph: &D, roots: impl IntoIterator<Item = T::Id>) -> IdSet<T::Id>
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    let mut dfs = Dfs::from_roots(graph, roots);
+    dfs.by_ref().for_each(drop);
+    dfs.seen
+}
+
+
+/**
+ * The strongly connected components reachable from `start`, by
+ * Tarjan's algorithm.
+ *
+ * Components are returned in reverse topological order: every
+ * component comes after all of the components it refers to, so the
+ * component containing `start` is last.
+ */
+pub fn components<T, D>(graph: &D, start: T::Id) -> Vec<Vec<T::Id>>
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    // Per-node discovery index and low-link, by id.
+    let mut index: Vec<Option<usize>> = Vec::new();
+    let mut low: Vec<usize> = Vec::new();
+    let mut on_stack = IdSet::new();
+    let mut stack: Vec<T::Id> = Vec::new();
+    let mut components = Vec::new();
+
+    let mut discovered = 0;
+    let mut discover = |id: T::Id, index: &mut Vec<Option<usize>>, low: &mut Vec<usize>| {
+        let i = id.index();
+        if i >= index.len() {
+            index.resize(i + 1, None);
+            low.resize(i + 1, 0);
+        }
+        index[i] = Some(discovered);
+        low[i] = discovered;
+        discovered += 1;
+    };
+
+    // Each frame is a node, its arguments, and how many of those
+    // have been visited.
+    let mut frames: Vec<(T::Id, Vec<T::Id>, usize)> = Vec::new();
+    discover(start, &mut index, &mut low);
+    on_stack.insert(start);
+    stack.push(start);
+    frames.push((start, graph.args(start).collect(), 0));
+
+    while let Some((id, args, next)) = frames.last_mut() {
+        let id = *id;
+        if let Some(arg) = args.get(*next).copied() {
+            *next += 1;
+            match index.get(arg.index()).copied().flatten() {
+                None => {
+                    discover(arg, &mut index, &mut low);
+                    on_stack.insert(arg);
+                    stack.push(arg);
+                    frames.push((arg, graph.args(arg).collect(), 0));
+                },
+                Some(d) if on_stack.contains(arg) => {
+                    low[id.index()] = low[id.index()].min(d);
+                },
+                Some(_) => (),
+            }
+        } else {
+            frames.pop();
+            let i = id.index();
+            if Some(low[i]) == index[i] {
+                let mut component = Vec::new();
+                loop {
+                    // `id` itself is on the stack, so this can't fail.
+                    let member = stack.pop().unwrap();
+                    on_stack.remove(member);
+                    component.push(member);
+                    if member == id {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some((parent, _, _)) = frames.last() {
+                let p = parent.index();
+                low[p] = low[p].min(low[i]);
+            }
+        }
+    }
+
+    components
+}
+
+
+/**
+ * Whether any cycle is reachable from `start`, including a node
+ * which refers to itself.
+ */
+pub fn is_cyclic<T, D>(graph: &D, start: T::Id) -> bool
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    components(graph, start).iter().any(|component| match component[..] {
+        [id] => graph.args(id).any(|arg| arg == id),
+        _ => true,
+    })
+}
+
+
+/**
+ * The nodes reachable from `start` ordered so that every node comes
+ * before its arguments, or `None` if there is a cycle.
+ */
+pub fn topological<T, D>(graph: &D, start: T::Id) -> Option<Vec<T::Id>>
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    if is_cyclic(graph, start) {
+        return None;
+    }
+    let mut order: Vec<T::Id> = PostOrder::new(graph, start).collect();
+    order.reverse();
+    Some(order)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::DataGraphBody;
+    use crate::grs::arena::VecGraph;
+    use crate::grs::strategy::tests::graph;
+    use crate::grs::tests::WideTypes;
+    use crate::grs::tests::Value::*;
+
+    #[test]
+    fn test_orders() {
+        // 0: Start 1 2, 1: Cons 3 2, 2: Succ 3, 3: Zero
+        let g = graph(&[(Start, &[1, 2]), (Cons, &[3, 2]), (Succ, &[3]), (Zero, &[])]);
+
+        assert_eq!(Dfs::new(&g, 0).collect::<Vec<_>>(), vec![0, 1, 3, 2]);
+        assert_eq!(Bfs::new(&g, 0).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(PostOrder::new(&g, 0).collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+        assert_eq!(topological(&g, 0), Some(vec![0, 1, 2, 3]));
+        assert!(!is_cyclic(&g, 0));
+
+        let set = reachable(&g, 1);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(!set.contains(0));
+        assert_eq!(set.len(), 3);
+
+        assert_eq!(Dfs::from_roots(&g, vec![2, 1]).collect::<Vec<_>>(), vec![2, 3, 1]);
+        assert_eq!(reachable_from(&g, vec![2, 3]).len(), 2);
+    }
+
+    #[test]
+    fn test_components() {
+        // 0: Start 1, 1: Cons 2 3, 2: Cons 1 4, 3: Succ 3, 4: Zero
+        let g = graph(&[
+            (Start, &[1]),
+            (Cons, &[2, 3]),
+            (Cons, &[1, 4]),
+            (Succ, &[3]),
+            (Zero, &[]),
+        ]);
+
+        assert_eq!(components(&g, 0), vec![vec![4], vec![3], vec![2, 1], vec![0]]);
+        assert!(is_cyclic(&g, 0));
+        assert!(!is_cyclic(&g, 4));
+        assert_eq!(topological(&g, 0), None);
+
+        // Only a self-loop.
+        assert!(is_cyclic(&g, 3));
+
+        // Traversals terminate on cycles.
+        assert_eq!(Dfs::new(&g, 0).count(), 5);
+        assert_eq!(PostOrder::new(&g, 0).collect::<Vec<_>>(), vec![4, 2, 3, 1, 0]);
+    }
+
+    #[test]
+    fn test_deep() {
+        // A long chain must not overflow the stack.
+        const DEPTH: u32 = 200_000;
+        let mut g: VecGraph<WideTypes> = VecGraph::new();
+        let mut prev = g.alloc(Succ);
+        for _ in 1..DEPTH {
+            let next = g.alloc(Succ);
+            g.append_arg(prev, next);
+            prev = next;
+        }
+        let zero = g.alloc(Zero);
+        g.append_arg(prev, zero);
+
+        let len = DEPTH as usize + 1;
+        assert_eq!(PostOrder::new(&g, 0).next(), Some(zero));
+        assert_eq!(components(&g, 0).len(), len);
+        assert!(!is_cyclic(&g, 0));
+        assert_eq!(topological(&g, 0).map(|order| order.len()), Some(len));
+    }
+}
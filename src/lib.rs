@@ -51,3 +51,4 @@ pub mod trs;
 pub mod grs;
 pub mod ast;
 pub mod parser;
+pub mod printer;
@@ -344,5 +344,7 @@ mod tests {
                 Token::ArrowTip
             ]
         );
+        // How `printer` renders an empty graph.
+        assert_eq!(lex("_"), vec![Token::Empty]);
     }
 }
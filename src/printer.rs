@@ -0,0 +1,236 @@
+// The MIT License (MIT)
+//
+// Copyright © 2022 <Brandon Lewis>
+//
+// Permission is hereby granted, free of charge, to any person
+// obtaining a copy of this software and associated documentation
+// files (the “Software”), to deal in the Software without
+// restriction, including without limitation the rights to use, copy,
+// modify, merge, publish, distribute, sublicense, and/or sell copies
+// of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED “AS IS”, WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS
+// BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN
+// ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+// Fork this project to create your own MIT license that you can
+// always link to.
+
+/*!
+ * This module renders data graphs in the shorthand notation whose
+ * grammar is given in `parser`.
+ *
+ * Every node is nested inside the node which refers to it, as in a
+ * term, except for nodes which are shared or cyclic. These are
+ * labeled with their id where they are first printed, and referred to
+ * by that id everywhere else. So `Cons x x` with a shared `x: Succ
+ * Zero` is printed as
+ *
+ * `Cons 1:(Succ Zero) 1`
+ *
+ * and the infinite list of zeros as
+ *
+ * `0: Cons Zero 0`
+ *
+ * An empty graph is printed as `_`, which the lexer reads as
+ * `Token::Empty`. (The grammar in `parser` spells this `nil`, but
+ * the lexer does not.)
+ *
+ * Values and ids are printed with their `Debug` representations, so
+ * a printed graph can only be read back if those agree with the
+ * `FromStr` implementations used by the lexer. In particular, a
+ * value whose `Debug` output looks like a tuple, such as `Int(1)`,
+ * won't parse: the grammar has no syntax for it.
+ *
+ * XXX: the parser itself is still a stub, so the round trip isn't
+ * tested yet.
+ */
+use core::fmt::{self, Display};
+use crate::grs::{DataGraph, DenseId, Types};
+use crate::grs::traverse::Dfs;
+
+
+/**
+ * Displays the subgraph reachable from a node in shorthand notation.
+ */
+pub struct Shorthand<'g, T: Types, D> {
+    graph: &'g D,
+    // `None` for an empty graph.
+    root: Option<T::Id>,
+}
+
+
+impl<'g, T, D> Shorthand<'g, T, D>
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    /**
+     * Display the whole graph, starting from its root.
+     */
+    pub fn new(graph: &'g D) -> Self {
+        Shorthand {graph, root: Some(graph.root())}
+    }
+
+    /**
+     * Display only the subgraph reachable from `root`.
+     */
+    pub fn at(graph: &'g D, root: T::Id) -> Self {
+        Shorthand {graph, root: Some(root)}
+    }
+
+    /**
+     * Display an empty graph, which has no root to start from.
+     */
+    pub fn empty(graph: &'g D) -> Self {
+        Shorthand {graph, root: None}
+    }
+
+    /**
+     * Which nodes need a label: those with more than one reference,
+     * counting the root position as a reference.
+     */
+    fn labels(&self, root: T::Id) -> Vec<bool> {
+        let mut refs: Vec<usize> = Vec::new();
+        let count = |refs: &mut Vec<usize>, id: T::Id| {
+            let i = id.index();
+            if i >= refs.len() {
+                refs.resize(i + 1, 0);
+            }
+            refs[i] += 1;
+        };
+
+        count(&mut refs, root);
+        for id in Dfs::new(self.graph, root) {
+            for arg in self.graph.args(id) {
+                count(&mut refs, arg);
+            }
+        }
+
+        refs.into_iter().map(|n| n > 1).collect()
+    }
+}
+
+
+// Pending output, in reverse order.
+enum Piece<Id> {
+    Text(&'static str),
+    // A node, and whether it appears as an argument.
+    Node(Id, bool),
+}
+
+
+impl<'g, T, D> Display for Shorthand<'g, T, D>
+where T: Types, T::Id: DenseId, D: DataGraph<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = match self.root {
+            Some(root) => root,
+            None => return f.write_str("_"),
+        };
+        let labels = self.labels(root);
+        let mut printed = vec![false; labels.len()];
+        let mut stack = vec![Piece::Node(root, false)];
+        let mut args = Vec::new();
+
+        while let Some(piece) = stack.pop() {
+            let (id, nested) = match piece {
+                Piece::Text(text) => {
+                    f.write_str(text)?;
+                    continue;
+                },
+                Piece::Node(id, nested) => (id, nested),
+            };
+
+            let i = id.index();
+            if labels[i] {
+                if printed[i] {
+                    write!(f, "{:?}", id)?;
+                    continue;
+                }
+                printed[i] = true;
+                write!(f, "{:?}:", id)?;
+                if !nested {
+                    f.write_str(" ")?;
+                }
+            }
+
+            args.extend(self.graph.args(id));
+            let parens = nested && !args.is_empty();
+            if parens {
+                f.write_str("(")?;
+                stack.push(Piece::Text(")"));
+            }
+            write!(f, "{:?}", self.graph.value(id))?;
+            for arg in args.drain(..).rev() {
+                stack.push(Piece::Node(arg, true));
+                stack.push(Piece::Text(" "));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grs::strategy::tests::graph;
+    use crate::grs::tests::Value::*;
+
+    #[test]
+    fn test_nesting() {
+        // Start (Add Zero (Succ Zero)), with the two Zeros distinct.
+        let g = graph(&[
+            (Start, &[1]),
+            (Add, &[2, 3]),
+            (Zero, &[]),
+            (Succ, &[4]),
+            (Zero, &[]),
+        ]);
+        assert_eq!(Shorthand::new(&g).to_string(), "Start (Add Zero (Succ Zero))");
+        assert_eq!(Shorthand::at(&g, 3).to_string(), "Succ Zero");
+        assert_eq!(Shorthand::at(&g, 4).to_string(), "Zero");
+        assert_eq!(Shorthand::empty(&g).to_string(), "_");
+    }
+
+    #[test]
+    fn test_sharing() {
+        // Cons x x, x: Succ Zero
+        let g = graph(&[(Cons, &[1, 1]), (Succ, &[2]), (Zero, &[])]);
+        assert_eq!(Shorthand::new(&g).to_string(), "Cons 1:(Succ Zero) 1");
+
+        // Start (Add Zero (Succ Zero)), with a single shared Zero.
+        let g = graph(&[(Start, &[1]), (Add, &[2, 3]), (Zero, &[]), (Succ, &[2])]);
+        assert_eq!(Shorthand::new(&g).to_string(), "Start (Add 2:Zero (Succ 2))");
+    }
+
+    #[test]
+    fn test_cycles() {
+        // x: Cons Zero x
+        let g = graph(&[(Cons, &[1, 0]), (Zero, &[])]);
+        assert_eq!(Shorthand::new(&g).to_string(), "0: Cons Zero 0");
+
+        // Start y, y: Cons Zero (Cons (Succ Zero) y)
+        let g = graph(&[
+            (Start, &[1]),
+            (Cons, &[2, 3]),
+            (Zero, &[]),
+            (Cons, &[4, 1]),
+            (Succ, &[5]),
+            (Zero, &[]),
+        ]);
+        assert_eq!(
+            Shorthand::new(&g).to_string(),
+            "Start 1:(Cons Zero (Cons (Succ Zero) 1))"
+        );
+    }
+}